@@ -0,0 +1,121 @@
+use std::{
+    collections::hash_map::{DefaultHasher, Entry},
+    hash::{Hash, Hasher},
+};
+
+use natural_syntax::{split_paragraphs, N_SPECIAL_TOKENS};
+
+use super::*;
+
+/// Tokens of tagged paragraphs, keyed by the hash of their text
+/// without trailing whitespace, with offsets relative to the paragraph.
+#[derive(Debug, Default)]
+pub struct ChunkCache {
+    paragraphs: HashMap<u64, Vec<POSToken>>,
+}
+
+/// Tag the paragraphs of `text` with `model`,
+/// reusing tokens from `cache` for paragraphs whose text is unchanged.
+/// Paragraphs that failed to tag are not cached, to report their errors again.
+/// Returns the tokens with offsets relative to `text`,
+/// the cache of the paragraphs in `text`, and errors tagging them.
+pub fn tag_paragraphs<T: Tagger + ?Sized>(
+    model: &T,
+    text: &str,
+    mut cache: ChunkCache,
+) -> (Vec<POSToken>, ChunkCache, Vec<Error>) {
+    let paragraphs = split_paragraphs(text)
+        .into_iter()
+        .map(|range| {
+            let paragraph = &text[range];
+            (paragraph, hash(paragraph.trim_end()))
+        })
+        .collect::<Vec<_>>();
+
+    let mut new_cache = ChunkCache::default();
+    let mut untagged = Vec::new();
+    for &(paragraph, hash) in &paragraphs {
+        if let Entry::Vacant(entry) = new_cache.paragraphs.entry(hash) {
+            match cache.paragraphs.remove(&hash) {
+                Some(tokens) => _ = entry.insert(tokens),
+                None => {
+                    entry.insert(Vec::new());
+                    untagged.push((paragraph.trim_end(), hash));
+                }
+            }
+        }
+    }
+    debug!(
+        n_paragraphs = paragraphs.len(),
+        n_untagged = untagged.len(),
+        "Tagging paragraphs."
+    );
+
+    // Batch paragraphs that fit in one pass, and window the rest.
+    let budget = model.max_input_length().saturating_sub(N_SPECIAL_TOKENS);
+    let (short, long): (Vec<_>, Vec<_>) = untagged
+        .into_iter()
+        .partition(|(paragraph, _)| model.count_tokens(paragraph) <= budget);
+    let short_inputs = short
+        .iter()
+        .map(|(paragraph, _)| *paragraph)
+        .collect::<Vec<_>>();
+    let results = model.predict_batch(&short_inputs).into_iter().chain(
+        long.iter()
+            .map(|(paragraph, _)| model.predict_long(paragraph)),
+    );
+    let (mut errors, mut failed) = (Vec::new(), Vec::new());
+    for ((_, hash), results) in short.iter().chain(&long).zip(results) {
+        let n_errors = errors.len();
+        let tokens = word_tokens(results, &mut errors);
+        if errors.len() > n_errors {
+            failed.push(*hash);
+        }
+        new_cache.paragraphs.insert(*hash, tokens);
+    }
+
+    let mut tokens = Vec::new();
+    let mut char_begin = 0;
+    for (paragraph, hash) in paragraphs {
+        tokens.extend(new_cache.paragraphs[&hash].iter().map(|token| POSToken {
+            offset_begin: token.offset_begin + char_begin,
+            offset_end: token.offset_end + char_begin,
+            ..token.clone()
+        }));
+        char_begin += paragraph.chars().count() as u32;
+    }
+    for hash in failed {
+        new_cache.paragraphs.remove(&hash);
+    }
+    (tokens, new_cache, errors)
+}
+
+/// Whole-word tokens from tagging `results`,
+/// collecting failures into `errors`.
+/// Tokens are filtered when encoded, so filters can change without re-tagging.
+fn word_tokens(results: Vec<Result<POSToken, Error>>, errors: &mut Vec<Error>) -> Vec<POSToken> {
+    let tokens = results
+        .into_iter()
+        .filter_map(|maybe_token| match maybe_token {
+            Ok(token) => Some(token),
+            Err(err) => {
+                error!(report = err.report(), "Tagging text.");
+                errors.push(err);
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    // Highlight whole words even if sub-word tokens were not consolidated.
+    let mut tokens = aggregate_words(tokens, AggregationStrategy::default())
+        .into_iter()
+        .map(POSToken::from)
+        .collect::<Vec<_>>();
+    tokens.sort_by_key(|token| token.offset_begin);
+    tokens
+}
+
+fn hash(paragraph: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    paragraph.hash(&mut hasher);
+    hasher.finish()
+}
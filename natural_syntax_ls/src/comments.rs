@@ -0,0 +1,322 @@
+use std::ops;
+
+use super::*;
+
+/// Comment syntax of a programming language,
+/// extracting the prose of its comments and doc strings.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CommentSyntax {
+    /// `//` and nested `/* */` comments, skipping strings, chars and raw strings.
+    Rust,
+    /// `#` comments and docstrings, skipping other strings.
+    Python,
+    /// `//` and `/* */` comments, skipping strings and template literals.
+    JavaScript,
+}
+
+impl ProseExtractor for CommentSyntax {
+    /// Byte ranges of the prose in the comments and doc strings of `text`,
+    /// without comment markers, code examples, inline code, doc tags or URLs.
+    fn prose_spans(&self, text: &str) -> Vec<ops::Range<usize>> {
+        let mut lexer = Lexer {
+            syntax: *self,
+            text,
+            index: 0,
+            last_comment_end: None,
+            in_fence: false,
+            spans: Vec::new(),
+        };
+        lexer.code();
+        lexer.spans
+    }
+}
+
+struct Lexer<'a> {
+    syntax: CommentSyntax,
+    text: &'a str,
+    index: usize,
+    /// End of the previous comment, to separate comments apart.
+    last_comment_end: Option<usize>,
+    /// If in a fenced code block of a doc comment.
+    in_fence: bool,
+    spans: Vec<ops::Range<usize>>,
+}
+
+impl Lexer<'_> {
+    /// Skip code until the end of the text, collecting prose from comments.
+    fn code(&mut self) {
+        use CommentSyntax::*;
+        while let Some(byte) = self.peek() {
+            let next = self.text.as_bytes().get(self.index + 1).copied();
+            match (self.syntax, byte, next) {
+                (Rust | JavaScript, b'/', Some(b'/')) | (Python, b'#', _) => self.line_comment(),
+                (Rust | JavaScript, b'/', Some(b'*')) => self.block_comment(),
+                (Python, b'"' | b'\'', _) => self.python_string(),
+                (Rust, b'r' | b'b', Some(b'#' | b'"' | b'r')) if !self.follows_identifier() => {
+                    self.raw_string()
+                }
+                (Rust, b'\'', _) => self.char_or_lifetime(),
+                (_, b'"' | b'\'' | b'`', _) => {
+                    self.index += 1;
+                    self.skip_string(byte);
+                }
+                _ => self.index += 1,
+            }
+        }
+    }
+
+    /// Collect the prose of the line comment at the current index.
+    fn line_comment(&mut self) {
+        self.begin_comment(self.index);
+        let line_end = self.line_end(self.index);
+        let comment = &self.text[self.index..line_end];
+        self.index = line_end;
+        self.last_comment_end = Some(line_end);
+        let content = match self.syntax {
+            CommentSyntax::Python => {
+                // Shebangs and tool directives are not prose.
+                let content = comment.trim_start_matches('#');
+                let directive = ["type:", "noqa", "pragma", "fmt:"]
+                    .iter()
+                    .any(|directive| content.trim_start().starts_with(directive));
+                if comment.starts_with("#!") || directive {
+                    return;
+                }
+                content
+            }
+            _ => {
+                let content = comment.trim_start_matches('/');
+                content.strip_prefix('!').unwrap_or(content)
+            }
+        };
+        self.comment_line(line_end - content.len()..line_end);
+    }
+
+    /// Collect the prose of the `/* */` comment at the current index.
+    fn block_comment(&mut self) {
+        self.begin_comment(self.index);
+        self.index += 2;
+        let content_start = self.index;
+        let mut depth = 1_usize;
+        let bytes = self.text.as_bytes();
+        let mut content_end = self.text.len();
+        while let Some(&byte) = bytes.get(self.index) {
+            match (byte, bytes.get(self.index + 1)) {
+                (b'*', Some(b'/')) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        content_end = self.index;
+                        self.index += 2;
+                        break;
+                    }
+                    self.index += 2;
+                }
+                (b'/', Some(b'*')) if self.syntax == CommentSyntax::Rust => {
+                    depth += 1;
+                    self.index += 2;
+                }
+                _ => self.index += 1,
+            }
+        }
+        if content_end == self.text.len() {
+            self.index = content_end;
+        }
+        let content = &self.text[content_start..content_end];
+        let content = content.trim_start_matches('*');
+        let content = content.strip_prefix('!').unwrap_or(content);
+        let mut line_start = content_end - content.len();
+        for line in content.split_inclusive('\n') {
+            let line_end = line_start + line.trim_end().len();
+            // Lines continuing the comment often start with `*`.
+            let text = line.trim_start();
+            let text = text.strip_prefix('*').unwrap_or(text);
+            let text_start = line_start + line.len() - text.len();
+            self.comment_line(text_start.min(line_end)..line_end);
+            line_start += line.len();
+        }
+        self.last_comment_end = Some(self.index);
+    }
+
+    /// Skip the Python string at the current index, collecting the prose of
+    /// triple-quoted strings that are statements on their own, as docstrings.
+    fn python_string(&mut self) {
+        let quote = self.text.as_bytes()[self.index];
+        let triple = [quote; 3];
+        if !self.text.as_bytes()[self.index..].starts_with(&triple) {
+            self.index += 1;
+            self.skip_string(quote);
+            return;
+        }
+        let line_start = self.text[..self.index]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let prefix = self.text[line_start..self.index].trim_start();
+        let is_docstring = prefix.len() <= 1 && prefix.chars().all(|char| "rRuU".contains(char));
+        self.index += 3;
+        let content_start = self.index;
+        let content_end = loop {
+            match self.text.as_bytes()[self.index..]
+                .iter()
+                .position(|&byte| byte == quote)
+            {
+                Some(offset)
+                    if self.text.as_bytes()[self.index + offset..].starts_with(&triple) =>
+                {
+                    let content_end = self.index + offset;
+                    let escaped = self.text[content_start..content_end].ends_with('\\');
+                    self.index = content_end + if escaped { 1 } else { 3 };
+                    if !escaped {
+                        break content_end;
+                    }
+                }
+                Some(offset) => self.index += offset + 1,
+                None => {
+                    self.index = self.text.len();
+                    break self.index;
+                }
+            }
+        };
+        if !is_docstring {
+            return;
+        }
+        self.begin_comment(content_start);
+        let mut line_start = content_start;
+        for line in self.text[content_start..content_end].split_inclusive('\n') {
+            let text = line.trim();
+            let text_start = line_start + line.len() - line.trim_start().len();
+            self.comment_line(text_start..text_start + text.len());
+            line_start += line.len();
+        }
+        self.last_comment_end = Some(self.index);
+    }
+
+    /// Skip the Rust raw or byte string at the current index,
+    /// or the identifier starting with `r` or `b`.
+    fn raw_string(&mut self) {
+        let rest = &self.text[self.index..];
+        let after_prefix = rest.strip_prefix("br").or(rest.strip_prefix('r'));
+        let Some(after_prefix) = after_prefix else {
+            // Byte strings, or identifiers.
+            self.index += 1;
+            if self.peek() == Some(b'"') {
+                self.index += 1;
+                self.skip_string(b'"');
+            }
+            return;
+        };
+        let hashes = after_prefix.len() - after_prefix.trim_start_matches('#').len();
+        if !after_prefix[hashes..].starts_with('"') {
+            self.index += 1;
+            return;
+        }
+        self.index += rest.len() - after_prefix.len() + hashes + 1;
+        let closing = format!("\"{}", "#".repeat(hashes));
+        self.index = match self.text[self.index..].find(&closing) {
+            Some(offset) => self.index + offset + closing.len(),
+            None => self.text.len(),
+        };
+    }
+
+    /// Skip the Rust char literal or lifetime at the current index.
+    fn char_or_lifetime(&mut self) {
+        self.index += 1;
+        let mut chars = self.text[self.index..].chars();
+        match (chars.next(), chars.next()) {
+            (Some('\\'), _) => self.skip_string(b'\''),
+            (Some(char), Some('\'')) => self.index += char.len_utf8() + 1,
+            // Lifetimes and labels.
+            _ => {}
+        }
+    }
+
+    /// Skip past the closing `quote` of the string before the current index.
+    fn skip_string(&mut self, quote: u8) {
+        let bytes = self.text.as_bytes();
+        while let Some(&byte) = bytes.get(self.index) {
+            self.index += 1;
+            match byte {
+                b'\\' => self.index += 1,
+                // Only Python strings and JavaScript quotes end at line ends.
+                b'\n' if quote != b'`' && self.syntax != CommentSyntax::Rust => return,
+                _ if byte == quote => return,
+                _ => {}
+            }
+        }
+        self.index = self.index.min(self.text.len());
+    }
+
+    /// Separate the comment starting at `start` from the previous one,
+    /// and leave fenced code blocks if code is between them.
+    fn begin_comment(&mut self, start: usize) {
+        push_break(start, &mut self.spans);
+        let after_code = self
+            .last_comment_end
+            .is_some_and(|end| !self.text[end..start].trim().is_empty());
+        if after_code {
+            self.in_fence = false;
+        }
+    }
+
+    /// Push the prose of the comment line in `range`,
+    /// without code examples, inline code, doc tags, type annotations or URLs.
+    fn comment_line(&mut self, range: ops::Range<usize>) {
+        let line = self.text[range.clone()].trim_start();
+        let range = range.end - line.len()..range.end;
+        if line.starts_with("```") {
+            self.in_fence = !self.in_fence;
+            return;
+        }
+        if self.in_fence || line.starts_with(">>>") || line.starts_with("...") {
+            return;
+        }
+        let (mut start, mut index) = (range.start, range.start);
+        while let Some(offset) = self.text[index..range.end].find(['`', '{', '@']) {
+            let markup = index + offset;
+            let rest = &self.text[markup..range.end];
+            let markup_end = match rest.as_bytes()[0] {
+                b'`' => rest[1..].find('`').map(|offset| markup + offset + 2),
+                b'{' => rest.find('}').map(|offset| markup + offset + 1),
+                // Doc tags, such as `@param`.
+                _ if self.text[..markup].ends_with(char::is_whitespace)
+                    || markup == range.start =>
+                {
+                    let tag_len = rest[1..]
+                        .find(char::is_whitespace)
+                        .unwrap_or(rest.len() - 1);
+                    Some(markup + 1 + tag_len)
+                }
+                _ => None,
+            };
+            let Some(markup_end) = markup_end else {
+                index = markup + 1;
+                continue;
+            };
+            push_without_urls(self.text, start..markup, &mut self.spans);
+            (start, index) = (markup_end, markup_end);
+        }
+        push_without_urls(self.text, start..range.end, &mut self.spans);
+    }
+
+    /// If the current index is right after an identifier character.
+    fn follows_identifier(&self) -> bool {
+        self.text[..self.index]
+            .chars()
+            .next_back()
+            .is_some_and(|char| char.is_alphanumeric() || char == '_')
+    }
+
+    /// Index of the end of the line at `index`, before its line break.
+    fn line_end(&self, index: usize) -> usize {
+        let end = self.text[index..]
+            .find('\n')
+            .map_or(self.text.len(), |offset| index + offset);
+        match self.text[..end].ends_with('\r') {
+            true => end - 1,
+            false => end,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.index).copied()
+    }
+}
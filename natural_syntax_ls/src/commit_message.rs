@@ -0,0 +1,115 @@
+use std::ops;
+
+use super::*;
+
+/// Language ID of Git commit messages.
+pub const GIT_COMMIT: &str = "gitcommit";
+
+/// Number of characters subject lines should fit in.
+const MAX_SUBJECT_LEN: usize = 50;
+
+/// Byte ranges of the prose in Git commit message `text`:
+/// the subject and body, without comment lines, trailers such as
+/// `Signed-off-by:`, or the scissors line and diff of `git commit --verbose`.
+pub fn git_commit_prose(text: &str) -> Vec<ops::Range<usize>> {
+    let mut spans = Vec::new();
+    for (start, line) in message_lines(text) {
+        if !is_trailer(line) {
+            push_without_urls(text, start..start + line.trim_end().len(), &mut spans);
+        }
+    }
+    spans
+}
+
+/// Diagnostics of the subject line of Git commit message `text`,
+/// given its tagged `tokens`:
+/// subject lines longer than 50 characters, and subject lines not in
+/// the imperative mood, whose first word after any `[scope]` or
+/// `type(scope):` prefix is tagged `VBD` or `VBZ`.
+pub fn subject_diagnostics(text: &Rope, tokens: &[POSToken]) -> Vec<Diagnostic> {
+    let source = text.to_string();
+    let Some((start, subject)) = message_lines(&source).find(|(_, line)| !line.trim().is_empty())
+    else {
+        return Vec::new();
+    };
+    let subject = subject.trim_end();
+    let subject_begin = text.byte_to_char(start);
+    let subject_end = subject_begin + subject.chars().count();
+    let diagnostic = |begin: usize, end: usize, message: String| Diagnostic {
+        range: Range {
+            start: char_to_position(text, begin),
+            end: char_to_position(text, end),
+        },
+        severity: Some(DiagnosticSeverity::WARNING),
+        source: Some(env!("CARGO_PKG_NAME").into()),
+        message,
+        ..Default::default()
+    };
+    let mut diagnostics = Vec::new();
+    let prefix_len = subject.len() - without_prefix(subject).len();
+    let words_begin = subject_begin + subject[..prefix_len].chars().count();
+    let first_word = tokens
+        .iter()
+        .skip_while(|token| (token.offset_begin as usize) < words_begin)
+        .take_while(|token| (token.offset_begin as usize) < subject_end)
+        .find(|token| token.word.chars().all(char::is_alphabetic));
+    if let Some(token) =
+        first_word.filter(|token| matches!(token.tag, PartOfSpeech::VBD | PartOfSpeech::VBZ))
+    {
+        let message = format!(
+            "Subject line should be in the imperative mood, but `{}` is tagged {:?}.",
+            token.word, token.tag
+        );
+        diagnostics.push(diagnostic(
+            token.offset_begin as usize,
+            token.offset_end as usize,
+            message,
+        ));
+    }
+    if subject_end - subject_begin > MAX_SUBJECT_LEN {
+        let message = format!(
+            "Subject line is {} characters long, over {MAX_SUBJECT_LEN}.",
+            subject_end - subject_begin
+        );
+        diagnostics.push(diagnostic(
+            subject_begin + MAX_SUBJECT_LEN,
+            subject_end,
+            message,
+        ));
+    }
+    diagnostics
+}
+
+/// Lines of the message in `text` with the byte offsets they start at,
+/// without comment lines and ending before the scissors line or diff.
+fn message_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    lines_with_offsets(text)
+        .take_while(|(_, line)| !line.contains("-- >8 --") && !line.starts_with("diff --git "))
+        .filter(|(_, line)| !line.starts_with('#'))
+}
+
+/// `subject` without its `[scope]` or `type(scope):` prefix, such as
+/// `[tagger]` or `fix(ls)!:`.
+fn without_prefix(subject: &str) -> &str {
+    if let Some(rest) = subject.strip_prefix('[') {
+        if let Some((_, rest)) = rest.split_once(']') {
+            return rest.trim_start();
+        }
+    }
+    match subject.split_once(':') {
+        Some((kind, rest)) if !kind.is_empty() && !kind.contains(char::is_whitespace) => {
+            rest.trim_start()
+        }
+        _ => subject,
+    }
+}
+
+/// If `line` is a trailer such as `Signed-off-by: Someone`.
+fn is_trailer(line: &str) -> bool {
+    line.split_once(": ").is_some_and(|(key, _)| {
+        key.contains('-')
+            && key
+                .chars()
+                .all(|char| char.is_ascii_alphanumeric() || char == '-')
+    })
+}
@@ -0,0 +1,64 @@
+use super::*;
+
+/// Number of integers encoding each [`SemanticToken`].
+const N_TOKEN_INTS: u32 = 5;
+
+/// Semantic tokens last sent for a document.
+#[derive(Debug)]
+pub struct SentTokens {
+    result_id: String,
+    data: Vec<SemanticToken>,
+}
+
+/// Reply with `data` under `result_id`,
+/// as edits against `sent` if the client has it as `previous_result_id`,
+/// and remember `data` as sent.
+pub fn reply_tokens(
+    sent: &mut Option<SentTokens>,
+    result_id: String,
+    previous_result_id: Option<&str>,
+    data: Vec<SemanticToken>,
+) -> SemanticTokensFullDeltaResult {
+    let reply = match sent {
+        Some(sent) if Some(sent.result_id.as_str()) == previous_result_id => {
+            SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+                result_id: Some(result_id.clone()),
+                edits: token_edits(&sent.data, &data),
+            })
+        }
+        _ => SemanticTokensFullDeltaResult::Tokens(SemanticTokens {
+            result_id: Some(result_id.clone()),
+            data: data.clone(),
+        }),
+    };
+    *sent = Some(SentTokens { result_id, data });
+    reply
+}
+
+/// Edits turning `old` into `new`,
+/// replacing the tokens between their common prefix and suffix.
+pub fn token_edits(old: &[SemanticToken], new: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
+    let n_prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let n_suffix = old[n_prefix..]
+        .iter()
+        .rev()
+        .zip(new[n_prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let (n_deleted, inserted) = (
+        old.len() - n_prefix - n_suffix,
+        &new[n_prefix..new.len() - n_suffix],
+    );
+    if n_deleted == 0 && inserted.is_empty() {
+        return Vec::new();
+    }
+    vec![SemanticTokensEdit {
+        start: n_prefix as u32 * N_TOKEN_INTS,
+        delete_count: n_deleted as u32 * N_TOKEN_INTS,
+        data: Some(inserted.to_vec()),
+    }]
+}
@@ -1,54 +1,226 @@
 use super::*;
 
-mod two;
-
-use two::*;
-
-pub struct DocumentRegistry {
-    model: Arc<POSModel>,
+pub struct DocumentRegistry<T: Tagger> {
+    model: Arc<T>,
+    /// Extractors of the prose to tag, by language ID.
+    extractors: Arc<ProseExtractors>,
     documents: HashMap<Url, DocumentStore>,
-    token_map: TokenMap,
+    highlighting: Highlighting,
+    /// Client to report tagging errors and request refreshes from.
+    client: Option<Client>,
+    /// If the client supports `workspace/semanticTokens/refresh`.
+    refresh_support: bool,
+    /// Number of semantic token results sent, for result IDs.
+    n_results: u64,
 }
 
-impl DocumentRegistry {
-    pub fn new(model: Arc<POSModel>) -> Self {
+impl<T: Tagger> DocumentRegistry<T> {
+    pub fn new(model: Arc<T>) -> Self {
         Self {
             model,
+            extractors: Default::default(),
             documents: Default::default(),
-            token_map: Default::default(),
+            highlighting: Default::default(),
+            client: None,
+            refresh_support: false,
+            n_results: 0,
+        }
+    }
+
+    /// Tag only the prose that `extractors` extract from documents.
+    pub fn with_extractors(mut self, extractors: ProseExtractors) -> Self {
+        self.extractors = Arc::new(extractors);
+        self
+    }
+
+    /// Report tagging errors to `client`, and request refreshes from it
+    /// if it supports them, see [`DocumentInfo::RefreshSupport`].
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    fn report_errors(&self, uri: &Url, errors: &[Error]) {
+        let Some(client) = self.client.clone() else {
+            return;
+        };
+        let messages = error_messages(uri, errors);
+        if !messages.is_empty() {
+            tokio::spawn(async move {
+                for message in messages {
+                    client.log_message(MessageType::WARNING, message).await;
+                }
+            });
         }
     }
+
+    /// Publish `diagnostics` of the document at `uri` at `version` to `client`.
+    fn publish_diagnostics(
+        client: Option<&Client>,
+        uri: Url,
+        diagnostics: Vec<Diagnostic>,
+        version: Option<i32>,
+    ) {
+        let Some(client) = client.cloned() else {
+            return;
+        };
+        tokio::spawn(async move { client.publish_diagnostics(uri, diagnostics, version).await });
+    }
+
+    /// Ask the client to request semantic tokens again.
+    fn request_refresh(&self) {
+        let Some(client) = self.client.clone().filter(|_| self.refresh_support) else {
+            return;
+        };
+        debug!("Requesting semantic token refresh.");
+        tokio::spawn(async move {
+            if let Err(err) = client.semantic_tokens_refresh().await {
+                error!(?err, "Requesting semantic token refresh.");
+            }
+        });
+    }
+
+    /// Reply to `reply` with the semantic tokens of `document`.
+    fn reply(
+        token_map: &TokenMap,
+        n_results: &mut u64,
+        sent: &mut Option<SentTokens>,
+        document: &Document,
+        reply: TokensReply,
+    ) {
+        if let Some(range) = reply.range {
+            let tokens = tokens_in_range(&document.text, &document.tokens, range);
+            let data = semantic_tokens(&document.text, tokens, token_map);
+            let result = SemanticTokensFullDeltaResult::Tokens(SemanticTokens {
+                result_id: None,
+                data,
+            });
+            reply.sender.send(result).drop_result();
+            return;
+        }
+        let data = semantic_tokens(&document.text, &document.tokens, token_map);
+        *n_results += 1;
+        let result = reply_tokens(
+            sent,
+            n_results.to_string(),
+            reply.previous_result_id.as_deref(),
+            data,
+        );
+        reply.sender.send(result).drop_result();
+    }
+}
+
+/// Request for the semantic tokens of a document.
+#[derive(Debug)]
+pub struct TokensRequest {
+    pub uri: Url,
+    /// Result ID of the tokens the client has, to reply with edits to.
+    pub previous_result_id: Option<String>,
+    /// Range to only reply with the tokens in, without a result ID.
+    pub range: Option<Range>,
+}
+
+#[derive(Debug)]
+struct TokensReply {
+    previous_result_id: Option<String>,
+    range: Option<Range>,
+    sender: oneshot::Sender<SemanticTokensFullDeltaResult>,
 }
 
 #[derive(Debug)]
 struct DocumentStore {
+    /// Language ID from the client.
+    language_id: String,
+    /// Latest text from the client, with changes applied.
+    text: Rope,
     /// The document waiting to be processed.
     queued: Option<TextItem>,
     /// If a document is being processed.
     processing: bool,
     /// The processed document.
     document: Option<Document>,
-    /// Replies to be made after processing the document.
-    delayed_replies: Two<oneshot::Sender<Vec<SemanticToken>>>,
+    /// Edits applied to `text` while a document is being processed,
+    /// with the version they made, to shift its tokens through.
+    edits: Vec<(i32, CharEdit)>,
+    /// If tokens were sent while the document was being processed,
+    /// so the client should refresh them after.
+    stale_sent: bool,
+    /// Full requests waiting for the first processed document,
+    /// made by clients that cannot refresh.
+    parked: Vec<TokensReply>,
+    /// Semantic tokens last sent to the client.
+    sent: Option<SentTokens>,
     latest_version: i32,
 }
 
 impl Default for DocumentStore {
     fn default() -> Self {
         Self {
+            language_id: Default::default(),
+            text: Default::default(),
             queued: Default::default(),
             processing: Default::default(),
             document: Default::default(),
-            delayed_replies: Default::default(),
+            edits: Default::default(),
+            stale_sent: Default::default(),
+            parked: Default::default(),
+            sent: Default::default(),
             latest_version: i32::MIN,
         }
     }
 }
 
-impl Actor for DocumentRegistry {
-    type Call = Url;
+impl DocumentStore {
+    /// The latest text of the document at `uri`, to process.
+    fn item(&self, uri: Url) -> TextItem {
+        TextItem {
+            uri,
+            language_id: self.language_id.clone(),
+            text: self.text.to_string(),
+            version: self.latest_version,
+        }
+    }
+
+    /// Apply `change` made by `version` to the text,
+    /// shifting the tokens of the stale document along.
+    fn apply(&mut self, version: i32, change: TextDocumentContentChangeEvent) {
+        let edit = apply_change(&mut self.text, change);
+        if let Some(document) = &mut self.document {
+            remap_tokens(&mut document.tokens, &edit);
+            document.text = self.text.clone();
+        }
+        // Predictions scheduled later start from the latest text.
+        if self.processing {
+            self.edits.push((version, edit));
+        }
+    }
+
+    /// Diagnostics of the processed document, if `highlighting` enables them.
+    fn diagnostics(&self, highlighting: &Highlighting) -> Vec<Diagnostic> {
+        match &self.document {
+            Some(document) if highlighting.diagnoses(&self.language_id) => {
+                subject_diagnostics(&document.text, &document.tokens)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Serve `document`, shifting its tokens through the edits made since.
+    fn set_document(&mut self, mut document: Document) {
+        self.edits
+            .retain(|(version, _)| *version > document.version);
+        for (_, edit) in &self.edits {
+            remap_tokens(&mut document.tokens, edit);
+        }
+        document.text = self.text.clone();
+        self.document = Some(document);
+    }
+}
+
+impl<T: Tagger> Actor for DocumentRegistry<T> {
+    type Call = TokensRequest;
     type Cast = DocumentInfo;
-    type Reply = Vec<SemanticToken>;
+    type Reply = SemanticTokensFullDeltaResult;
 
     async fn handle_cast(&mut self, msg: Self::Cast, env: &mut ActorEnv<Self>) -> Result<()> {
         match msg {
@@ -60,34 +232,146 @@ impl Actor for DocumentRegistry {
                         item.version, "Scheduling processing latest-version document."
                     );
                     store.latest_version = item.version;
-                    schedule_document_processing(item, store, &self.model, &env.ref_);
+                    store.language_id.clone_from(&item.language_id);
+                    let change = TextDocumentContentChangeEvent {
+                        range: None,
+                        range_length: None,
+                        text: item.text.clone(),
+                    };
+                    store.apply(item.version, change);
+                    if self.highlighting.enables(&store.language_id) {
+                        schedule_document_processing(
+                            item,
+                            store,
+                            &self.model,
+                            &self.extractors,
+                            &env.ref_,
+                        );
+                    }
+                }
+            }
+            DocumentInfo::Changes(VersionedTextDocumentIdentifier { uri, version }, changes) => {
+                let Some(store) = self.documents.get_mut(&uri) else {
+                    warn!(
+                        uri = uri.path(),
+                        version, "Ignoring changes to unopened document."
+                    );
+                    return Ok(());
+                };
+                if store.latest_version >= version {
+                    warn!(
+                        uri = uri.path(),
+                        version, store.latest_version, "Rejecting out-of-order changes."
+                    );
+                    return Ok(());
+                }
+                debug!(
+                    uri = uri.path(),
+                    version,
+                    n_changes = changes.len(),
+                    "Applying changes."
+                );
+                for change in changes {
+                    store.apply(version, change);
+                }
+                store.latest_version = version;
+                if self.highlighting.enables(&store.language_id) {
+                    let item = store.item(uri);
+                    schedule_document_processing(
+                        item,
+                        store,
+                        &self.model,
+                        &self.extractors,
+                        &env.ref_,
+                    );
                 }
             }
-            DocumentInfo::Predicted(uri, document) => {
+            DocumentInfo::Predicted(uri, document, errors) => {
                 debug!(uri = uri.path(), document.version, "Received prediction.");
+                self.report_errors(&uri, &errors);
                 if let Some(store) = self.documents.get_mut(&uri) {
                     store.processing = false;
-                    let maybe_reply = match store.queued {
-                        // Leave one reply for after the new document is processed.
-                        Some(_) => store.delayed_replies.take_older(),
-                        None => store.delayed_replies.take_newer_n_clear(),
-                    };
-                    if let Some(reply) = maybe_reply {
-                        debug!(uri = uri.path(), "Sending delayed reply.");
-                        let tokens =
-                            semantic_tokens(&document.text, &document.tokens, &self.token_map);
-                        reply.send(tokens).drop_result();
+                    store.set_document(document);
+                    if let Some(document) = &store.document {
+                        for reply in store.parked.drain(..) {
+                            debug!(uri = uri.path(), "Sending parked reply.");
+                            Self::reply(
+                                self.highlighting.token_map(&store.language_id),
+                                &mut self.n_results,
+                                &mut store.sent,
+                                document,
+                                reply,
+                            );
+                        }
                     }
-                    store.document = Some(document);
+                    if self.highlighting.diagnoses(&store.language_id) {
+                        let diagnostics = store.diagnostics(&self.highlighting);
+                        let version = Some(store.latest_version);
+                        Self::publish_diagnostics(self.client.as_ref(), uri, diagnostics, version);
+                    }
+                    let refresh = mem::take(&mut store.stale_sent);
                     if let Some(queued) = store.queued.take() {
-                        schedule_document_processing(queued, store, &self.model, &env.ref_);
+                        schedule_document_processing(
+                            queued,
+                            store,
+                            &self.model,
+                            &self.extractors,
+                            &env.ref_,
+                        );
+                    }
+                    if refresh {
+                        self.request_refresh();
                     }
                 } else {
                     debug!("Discarding uninteresting prediction.");
                 }
             }
-            DocumentInfo::Discard(uri) => _ = self.documents.remove(&uri),
-            DocumentInfo::TokenMapUpdate(update) => self.token_map.extend(update),
+            DocumentInfo::Discard(uri) => {
+                let store = self.documents.remove(&uri);
+                if store.is_some_and(|store| self.highlighting.diagnoses(&store.language_id)) {
+                    Self::publish_diagnostics(self.client.as_ref(), uri, Vec::new(), None);
+                }
+            }
+            DocumentInfo::RefreshSupport(refresh_support) => self.refresh_support = refresh_support,
+            DocumentInfo::Configure(settings) => {
+                debug!(?settings, "Applying settings.");
+                let diagnosed = self.highlighting.diagnoses(GIT_COMMIT);
+                self.highlighting.apply(settings);
+                // Publish or clear diagnostics just toggled.
+                if self.highlighting.diagnoses(GIT_COMMIT) != diagnosed {
+                    for (uri, store) in &self.documents {
+                        if store.language_id == GIT_COMMIT {
+                            let diagnostics = store.diagnostics(&self.highlighting);
+                            let version = Some(store.latest_version);
+                            Self::publish_diagnostics(
+                                self.client.as_ref(),
+                                uri.clone(),
+                                diagnostics,
+                                version,
+                            );
+                        }
+                    }
+                }
+                // Tag documents just enabled.
+                for (uri, store) in &mut self.documents {
+                    let tagged_version = store.document.as_ref().map(|document| document.version);
+                    let outdated = tagged_version.unwrap_or(i32::MIN) < store.latest_version;
+                    if outdated
+                        && !store.processing
+                        && self.highlighting.enables(&store.language_id)
+                    {
+                        let item = store.item(uri.clone());
+                        schedule_document_processing(
+                            item,
+                            store,
+                            &self.model,
+                            &self.extractors,
+                            &env.ref_,
+                        );
+                    }
+                }
+                self.request_refresh();
+            }
         }
         Ok(())
     }
@@ -98,23 +382,69 @@ impl Actor for DocumentRegistry {
         _env: &mut ActorEnv<Self>,
         reply_sender: oneshot::Sender<Self::Reply>,
     ) -> Result<()> {
-        let store = self.documents.entry(msg).or_default();
-        match (store.processing, &store.document) {
-            (false, Some(Document { text, tokens, .. })) => {
-                let tokens = semantic_tokens(text, tokens, &self.token_map);
-                reply_sender.send(tokens).drop_result();
+        let TokensRequest {
+            uri,
+            previous_result_id,
+            range,
+        } = msg;
+        let store = self.documents.entry(uri).or_default();
+        let reply = TokensReply {
+            previous_result_id,
+            range,
+            sender: reply_sender,
+        };
+        if !self.highlighting.enables(&store.language_id) {
+            let result = SemanticTokensFullDeltaResult::Tokens(SemanticTokens::default());
+            reply.sender.send(result).drop_result();
+            return Ok(());
+        }
+        // Answer right away, and have the client refresh once processed.
+        let can_refresh = self.client.is_some() && self.refresh_support;
+        store.stale_sent |= store.processing;
+        match (store.processing, &store.document, range) {
+            (_, Some(document), _) => Self::reply(
+                self.highlighting.token_map(&store.language_id),
+                &mut self.n_results,
+                &mut store.sent,
+                document,
+                reply,
+            ),
+            // Tag the requested range first while the whole document is being tagged.
+            (true, None, Some(range)) => {
+                let (model, extractors, language_id, text, token_map) = (
+                    self.model.clone(),
+                    self.extractors.clone(),
+                    store.language_id.clone(),
+                    store.text.clone(),
+                    self.highlighting.token_map(&store.language_id).clone(),
+                );
+                spawn_blocking(move || {
+                    let extractor = extractors.get(&language_id);
+                    let data = tag_range(&*model, extractor, &text, range, &token_map);
+                    let result = SemanticTokensFullDeltaResult::Tokens(SemanticTokens {
+                        result_id: None,
+                        data,
+                    });
+                    reply.sender.send(result).drop_result();
+                });
+            }
+            // Empty tokens would stay until the next request without refreshes.
+            (true, None, None) if !can_refresh => store.parked.push(reply),
+            (_, None, _) => {
+                let result = SemanticTokensFullDeltaResult::Tokens(SemanticTokens::default());
+                reply.sender.send(result).drop_result();
             }
-            _ => _ = store.delayed_replies.push(reply_sender),
         }
         Ok(())
     }
 }
 
-fn schedule_document_processing(
+fn schedule_document_processing<T: Tagger>(
     item: TextItem,
     store: &mut DocumentStore,
-    model: &Arc<POSModel>,
-    ref_: &ActorRef<DocumentRegistry>,
+    model: &Arc<T>,
+    extractors: &Arc<ProseExtractors>,
+    ref_: &ActorRef<DocumentRegistry<T>>,
 ) {
     if store.processing {
         debug!(
@@ -124,18 +454,30 @@ fn schedule_document_processing(
         store.queued = Some(item);
     } else {
         (store.processing, store.queued) = (true, None);
-        let (model, actor_ref) = (model.clone(), ref_.clone());
-        spawn_blocking(move || predict(model, item, actor_ref));
+        let chunks = store
+            .document
+            .as_mut()
+            .map(|document| mem::take(&mut document.chunks))
+            .unwrap_or_default();
+        let (model, extractors, actor_ref) = (model.clone(), extractors.clone(), ref_.clone());
+        spawn_blocking(move || predict(model, &extractors, item, chunks, actor_ref));
     }
 }
 
 pub enum DocumentInfo {
     /// Document item from the language client.
     Item(TextItem),
-    /// Predicted tokens for the document.
-    Predicted(Url, Document),
+    /// Changes to apply in order to an opened document.
+    Changes(
+        VersionedTextDocumentIdentifier,
+        Vec<TextDocumentContentChangeEvent>,
+    ),
+    /// Predicted tokens for the document, and errors tagging it.
+    Predicted(Url, Document, Vec<Error>),
     /// Forget about the document.
     Discard(Url),
-    /// Instruction to update the token map.
-    TokenMapUpdate(HashMap<PartOfSpeech, Option<TokenTypeNModifiers>>),
+    /// If the client supports `workspace/semanticTokens/refresh`.
+    RefreshSupport(bool),
+    /// Settings to apply, updating the token map among others.
+    Configure(Settings),
 }
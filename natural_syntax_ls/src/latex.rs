@@ -0,0 +1,288 @@
+use std::ops;
+
+use super::*;
+
+/// Commands whose mandatory arguments are prose, by argument.
+const PROSE_COMMANDS: &[(&str, &[bool])] = &[
+    ("caption", &[true]),
+    ("chapter", &[true]),
+    ("emph", &[true]),
+    ("footnote", &[true]),
+    ("footnotetext", &[true]),
+    ("href", &[false, true]),
+    ("marginpar", &[true]),
+    ("mbox", &[true]),
+    ("paragraph", &[true]),
+    ("part", &[true]),
+    ("section", &[true]),
+    ("subparagraph", &[true]),
+    ("subsection", &[true]),
+    ("subsubsection", &[true]),
+    ("subtitle", &[true]),
+    ("text", &[true]),
+    ("textbf", &[true]),
+    ("textit", &[true]),
+    ("textmd", &[true]),
+    ("textnormal", &[true]),
+    ("textrm", &[true]),
+    ("textsc", &[true]),
+    ("textsf", &[true]),
+    ("textsl", &[true]),
+    ("textup", &[true]),
+    ("thanks", &[true]),
+    ("title", &[true]),
+    ("underline", &[true]),
+];
+
+/// Commands of [`PROSE_COMMANDS`] whose arguments are apart from
+/// the text around them, rather than inline.
+const SEPARATE_COMMANDS: &[&str] = &[
+    "caption",
+    "chapter",
+    "footnote",
+    "footnotetext",
+    "marginpar",
+    "paragraph",
+    "part",
+    "section",
+    "subparagraph",
+    "subsection",
+    "subsubsection",
+    "subtitle",
+    "thanks",
+    "title",
+];
+
+/// Commands that break words, such as spaces and line breaks.
+const BREAKING_COMMANDS: &[&str] = &[
+    "enskip",
+    "enspace",
+    "hfill",
+    "hspace",
+    "linebreak",
+    "newline",
+    "par",
+    "qquad",
+    "quad",
+];
+
+/// Environments whose content is math or verbatim rather than prose.
+const SKIPPED_ENVIRONMENTS: &[&str] = &[
+    "align",
+    "align*",
+    "alignat",
+    "alignat*",
+    "comment",
+    "displaymath",
+    "eqnarray",
+    "eqnarray*",
+    "equation",
+    "equation*",
+    "filecontents",
+    "flalign",
+    "flalign*",
+    "gather",
+    "gather*",
+    "lstlisting",
+    "math",
+    "minted",
+    "multline",
+    "multline*",
+    "tikzpicture",
+    "verbatim",
+    "verbatim*",
+    "Verbatim",
+];
+
+/// Byte ranges of the prose in LaTeX `text`:
+/// text outside commands, and the arguments of commands like `\emph` and
+/// `\section`, without comments, math, verbatim or other arguments.
+pub fn latex_prose(text: &str) -> Vec<ops::Range<usize>> {
+    let mut lexer = Lexer {
+        text,
+        index: 0,
+        spans: Vec::new(),
+    };
+    lexer.prose(false);
+    lexer.spans
+}
+
+struct Lexer<'a> {
+    text: &'a str,
+    index: usize,
+    spans: Vec<ops::Range<usize>>,
+}
+
+impl<'a> Lexer<'a> {
+    /// Collect prose until the end of the text,
+    /// or the `}` closing the current group if `in_group`.
+    fn prose(&mut self, in_group: bool) {
+        while let Some(byte) = self.peek() {
+            match byte {
+                b'}' if in_group => {
+                    self.index += 1;
+                    return;
+                }
+                b'%' => self.skip_past("\n"),
+                b'$' if self.text[self.index..].starts_with("$$") => {
+                    self.index += 2;
+                    self.skip_past("$$");
+                }
+                b'$' => {
+                    self.index += 1;
+                    self.skip_past("$");
+                }
+                b'\\' => self.command(),
+                b'{' => {
+                    self.index += 1;
+                    self.prose(true);
+                }
+                b'~' => {
+                    push_break(self.index, &mut self.spans);
+                    self.index += 1;
+                }
+                b'}' => self.index += 1,
+                _ => {
+                    let start = self.index;
+                    let end = self.text[start..]
+                        .find(['}', '%', '$', '\\', '{', '~'])
+                        .map_or(self.text.len(), |offset| start + offset);
+                    push_span(start..end, &mut self.spans);
+                    self.index = end;
+                }
+            }
+        }
+    }
+
+    /// Handle the command at the backslash at the current index.
+    fn command(&mut self) {
+        self.index += 1;
+        let name_len = self.text[self.index..]
+            .find(|char: char| !char.is_ascii_alphabetic() && char != '@')
+            .unwrap_or(self.text.len() - self.index);
+        let name = &self.text[self.index..self.index + name_len];
+        self.index += name_len;
+        match name {
+            // Control symbols, such as `\%`, `\\` and inline or display math.
+            "" => match self.peek() {
+                Some(b'(') => self.skip_past("\\)"),
+                Some(b'[') => self.skip_past("\\]"),
+                _ => {
+                    let symbol = self.text[self.index..].chars().next();
+                    // Line breaks and spaces, such as `\\`, `\ ` and `\,`.
+                    if symbol.is_some_and(|symbol| "\\ ,:;>".contains(symbol)) {
+                        push_break(self.index - 1, &mut self.spans);
+                    }
+                    self.index += symbol.map_or(0, char::len_utf8);
+                }
+            },
+            "verb" => {
+                self.skip_star();
+                if let Some(delimiter) = self.text[self.index..].chars().next() {
+                    self.index += delimiter.len_utf8();
+                    self.skip_past(delimiter.encode_utf8(&mut [0; 4]));
+                }
+            }
+            "begin" => {
+                let environment = self.group_text();
+                if SKIPPED_ENVIRONMENTS.contains(&environment) {
+                    self.skip_past(&format!("\\end{{{environment}}}"));
+                } else {
+                    self.skip_arguments(&[]);
+                }
+            }
+            _ => {
+                let prose_arguments = PROSE_COMMANDS
+                    .iter()
+                    .find(|(command, _)| *command == name)
+                    .map_or(&[][..], |(_, arguments)| arguments);
+                let breaks_words =
+                    SEPARATE_COMMANDS.contains(&name) || BREAKING_COMMANDS.contains(&name);
+                if breaks_words {
+                    push_break(self.index - name.len() - 1, &mut self.spans);
+                }
+                self.skip_arguments(prose_arguments);
+                if breaks_words {
+                    push_break(self.index, &mut self.spans);
+                }
+            }
+        }
+    }
+
+    /// Skip a star and the arguments right after a command,
+    /// collecting prose from the mandatory arguments that are prose.
+    fn skip_arguments(&mut self, prose_arguments: &[bool]) {
+        self.skip_star();
+        let mut n_mandatory = 0;
+        loop {
+            match self.peek() {
+                Some(b'[') => self.skip_group(b'[', b']'),
+                Some(b'{') if prose_arguments.get(n_mandatory) == Some(&true) => {
+                    self.index += 1;
+                    self.prose(true);
+                    n_mandatory += 1;
+                }
+                Some(b'{') => {
+                    self.skip_group(b'{', b'}');
+                    n_mandatory += 1;
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// The text of the `{}` group at the current index, skipping it.
+    fn group_text(&mut self) -> &'a str {
+        let start = self.index;
+        self.skip_group(b'{', b'}');
+        let end = self.index;
+        self.text[start..end]
+            .strip_prefix('{')
+            .and_then(|group| group.strip_suffix('}'))
+            .unwrap_or_default()
+    }
+
+    /// Skip the group from `open` at the current index to its matching `close`.
+    fn skip_group(&mut self, open: u8, close: u8) {
+        if self.peek() != Some(open) {
+            return;
+        }
+        let mut depth = 0_usize;
+        let bytes = self.text.as_bytes();
+        while let Some(&byte) = bytes.get(self.index) {
+            self.index += 1;
+            match byte {
+                b'\\' => {
+                    let escaped = self.text[self.index..].chars().next();
+                    self.index += escaped.map_or(0, char::len_utf8);
+                }
+                _ if byte == open => depth += 1,
+                _ if byte == close => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn skip_star(&mut self) {
+        if self.peek() == Some(b'*') {
+            self.index += 1;
+        }
+    }
+
+    /// Skip past the next `pattern`, or to the end of the text.
+    fn skip_past(&mut self, pattern: &str) {
+        self.index = match self.text[self.index..].find(pattern) {
+            Some(offset) => self.index + offset + pattern.len(),
+            None => self.text.len(),
+        };
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.index).copied()
+    }
+}
@@ -1,53 +1,165 @@
-use std::{collections::HashMap, mem, sync::Arc};
+use std::{
+    collections::HashMap,
+    mem,
+    sync::{Arc, OnceLock},
+};
 
 use anyhow::Result;
 use drop_this::*;
-use natural_syntax::{POSModel, POSToken, PartOfSpeech, N_PART_OF_SPEECH};
+use natural_syntax::{
+    aggregate_words, AggregationStrategy, Error, POSToken, PartOfSpeech, Tagger, UniversalPos,
+    N_PART_OF_SPEECH,
+};
 use num::FromPrimitive;
 use num_derive::FromPrimitive;
-use ropey::Rope;
+use ropey::{Rope, RopeSlice};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tokio::{
     io::{stdin, stdout},
     sync::oneshot,
-    task::{block_in_place, spawn_blocking},
+    task::spawn_blocking,
 };
 use tokio_gen_server::prelude::*;
 use tower_lsp::{
     jsonrpc::Result as JsonRes, lsp_types::*, Client, LanguageServer, LspService, Server,
 };
+#[cfg(feature = "rust-bert")]
+use {natural_syntax::POSModelBuilder, tokio::task::block_in_place};
 
+mod chunks;
+mod comments;
+mod commit_message;
+mod delta;
 mod document_registry;
+mod latex;
+mod markdown;
+mod org;
+mod prose;
+mod restructured_text;
 mod semantic_tokens;
+mod settings;
+mod text_sync;
 mod token_mapping;
+mod unavailable;
+mod viewport;
 
+use chunks::*;
+use comments::*;
+use commit_message::*;
+use delta::*;
 use document_registry::*;
+use latex::*;
+use markdown::*;
+use org::*;
+use prose::*;
+pub use prose::{ProseExtractor, ProseExtractors};
+use restructured_text::*;
 use semantic_tokens::*;
+use settings::*;
+use text_sync::*;
 use token_mapping::*;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
+pub use unavailable::run_ls_unavailable;
+use viewport::*;
+
+/// Run the Part of Speech Language Server that provides highlighting,
+/// with the model from `model_builder`.
+/// If the model fails to load, the server reports why to the client
+/// instead of exiting.
+#[cfg(feature = "rust-bert")]
+pub async fn run_part_of_speech_ls(model_builder: POSModelBuilder) -> Result<()> {
+    match block_in_place(|| model_builder.build()) {
+        Ok(model) => {
+            info!("Model loaded.");
+            run_ls_with_tagger(model).await
+        }
+        Err(err) => {
+            error!(report = err.report(), "Loading the model.");
+            run_ls_unavailable(err).await
+        }
+    }
+}
+
+/// Run the Part of Speech Language Server with `model` as the tagger.
+pub async fn run_ls_with_tagger<T: Tagger>(model: T) -> Result<()> {
+    run_ls_with_extractors(model, ProseExtractors::default()).await
+}
 
-/// Run the Part of Speech Language Server that provides highlighting.
-pub async fn run_part_of_speech_ls() -> Result<()> {
-    let model = block_in_place(POSModel::try_default)?;
-    info!("Model loaded.");
-    let (service, socket) = LspService::build(|client| POSLS::new(client, model)).finish();
+/// Run the Part of Speech Language Server with `model` as the tagger,
+/// tagging only the prose that `extractors` extract from documents.
+pub async fn run_ls_with_extractors<T: Tagger>(
+    model: T,
+    extractors: ProseExtractors,
+) -> Result<()> {
+    let (service, socket) =
+        LspService::build(|client| POSLS::with_extractors(client, model, extractors)).finish();
     Server::new(stdin(), stdout(), socket).serve(service).await;
     Ok(())
 }
 
-pub struct POSLS {
+pub struct POSLS<T: Tagger> {
     client: Client,
-    document_registry: ActorRef<DocumentRegistry>,
+    document_registry: ActorRef<DocumentRegistry<T>>,
+    /// Set on initialization.
+    configuration_support: OnceLock<ConfigurationSupport>,
 }
 
-const TOKEN_SCORE_THRESHOLD: f64 = 1. / 3.;
+impl<T: Tagger> POSLS<T> {
+    pub fn new(client: Client, model: T) -> Self {
+        Self::with_extractors(client, model, ProseExtractors::default())
+    }
 
-impl POSLS {
-    pub fn new(client: Client, model: POSModel) -> Self {
-        let document_registry = DocumentRegistry::new(Arc::new(model));
+    pub fn with_extractors(client: Client, model: T, extractors: ProseExtractors) -> Self {
+        let document_registry = DocumentRegistry::new(Arc::new(model))
+            .with_extractors(extractors)
+            .with_client(client.clone());
         Self {
             client,
             document_registry: document_registry.spawn().1,
+            configuration_support: OnceLock::new(),
+        }
+    }
+
+    fn configuration_support(&self) -> ConfigurationSupport {
+        self.configuration_support
+            .get()
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Apply the settings in `value` from `source`,
+    /// reporting invalid settings to the client.
+    async fn apply_settings(&self, value: Value, source: &str) {
+        match Settings::from_value(value) {
+            Ok(Some(settings)) => self
+                .document_registry
+                .cast(DocumentInfo::Configure(settings))
+                .await
+                .unwrap(),
+            Ok(None) => debug!(source, "No settings."),
+            Err(err) => {
+                error!(?err, source, "Parsing settings.");
+                self.client
+                    .log_message(MessageType::ERROR, format!("Invalid {source}: {err:?}"))
+                    .await;
+            }
+        }
+    }
+
+    /// Request the settings from the client and apply them.
+    async fn pull_settings(&self) {
+        let item = ConfigurationItem {
+            scope_uri: None,
+            section: Some(SETTINGS_SECTION.into()),
+        };
+        match self.client.configuration(vec![item]).await {
+            Ok(values) => {
+                for value in values {
+                    self.apply_settings(value, "settings").await;
+                }
+            }
+            Err(err) => error!(?err, "Requesting settings."),
         }
     }
 
@@ -59,94 +171,143 @@ impl POSLS {
     }
 }
 
-fn predict(model: Arc<POSModel>, item: TextItem, actor_ref: ActorRef<DocumentRegistry>) {
+fn predict<T: Tagger>(
+    model: Arc<T>,
+    extractors: &ProseExtractors,
+    item: TextItem,
+    chunks: ChunkCache,
+    actor_ref: ActorRef<DocumentRegistry<T>>,
+) {
     debug!(uri = item.uri.path(), item.version, "Predicting.");
-    let mut tokens = model
-        .predict(&item.text)
-        .filter_map(|maybe_token| match maybe_token {
-            Ok(token) => Some(token),
-            Err(err) => {
-                error!(?err, ?item.uri, "Tagging text.");
-                None
-            }
-        })
-        .filter(filter_token)
-        .collect::<Vec<_>>();
-    tokens.sort_by_key(|token| token.offset_begin);
+    let extractor = extractors.get(&item.language_id);
+    let (tokens, chunks, errors) = tag_document(&*model, extractor, &item.text, chunks);
     let document = Document {
         text: Rope::from_str(&item.text),
         tokens,
         version: item.version,
+        chunks,
     };
     actor_ref
-        .blocking_cast(DocumentInfo::Predicted(item.uri, document))
+        .blocking_cast(DocumentInfo::Predicted(item.uri, document, errors))
         .drop_result();
 }
 
-/// Filter out tokens with low score or purely punctuations.
-pub fn filter_token(token: &POSToken) -> bool {
-    token.score > TOKEN_SCORE_THRESHOLD
-        && token.word.chars().any(|char| !char.is_ascii_punctuation())
+/// Distinct messages for the client about `errors` tagging `uri`.
+fn error_messages(uri: &Url, errors: &[Error]) -> Vec<String> {
+    let mut messages: Vec<String> = Vec::new();
+    for err in errors {
+        let message = format!(
+            "Some words in `{uri}` are not highlighted: {}.",
+            err.report()
+        );
+        if !messages.contains(&message) {
+            messages.push(message);
+        }
+    }
+    messages
 }
 
 #[tower_lsp::async_trait]
-impl LanguageServer for POSLS {
+impl<T: Tagger> LanguageServer for POSLS<T> {
     async fn initialize(
         &self,
         InitializeParams {
             initialization_options,
+            capabilities,
             ..
         }: InitializeParams,
     ) -> JsonRes<InitializeResult> {
-        if let Some(InitializationOptions {
-            token_map_update: Some(update),
-        }) = initialization_options.and_then(|options| {
-            serde_json::from_value(options)
-                .map_err(|err| {
-                    error!(?err, "Initialization options.");
-                    self.client.log_message(
-                        MessageType::ERROR,
-                        format!("Invalid initialization options: {err:?}"),
-                    )
-                })
-                .ok()
-        }) {
-            debug!(?update, "Token map options.");
-            self.document_registry
-                .cast(DocumentInfo::TokenMapUpdate(update))
-                .await
-                .unwrap();
+        let configuration_support = ConfigurationSupport::new(&capabilities);
+        let refresh_support = capabilities
+            .workspace
+            .and_then(|workspace| workspace.semantic_tokens)
+            .and_then(|semantic_tokens| semantic_tokens.refresh_support)
+            .unwrap_or_default();
+        debug!(
+            refresh_support,
+            ?configuration_support,
+            "Client capabilities."
+        );
+        self.configuration_support
+            .set(configuration_support)
+            .drop_result();
+        if let Some(options) = initialization_options {
+            self.apply_settings(options, "initialization options").await;
         }
+        // Only after the settings, to not request refreshes before initializing.
+        self.document_registry
+            .cast(DocumentInfo::RefreshSupport(refresh_support))
+            .await
+            .unwrap();
         Ok(InitializeResult {
             capabilities: server_capabilities(),
             ..Default::default()
         })
     }
 
+    async fn initialized(&self, _: InitializedParams) {
+        let support = self.configuration_support();
+        if support.dynamic_registration {
+            let registration = Registration {
+                id: "did-change-configuration".into(),
+                method: "workspace/didChangeConfiguration".into(),
+                register_options: None,
+            };
+            if let Err(err) = self.client.register_capability(vec![registration]).await {
+                error!(?err, "Registering for configuration changes.");
+            }
+        }
+        if support.pull {
+            self.pull_settings().await;
+        }
+    }
+
+    async fn did_change_configuration(
+        &self,
+        DidChangeConfigurationParams { settings }: DidChangeConfigurationParams,
+    ) {
+        info!("Configuration changed.");
+        // Clients that answer `workspace/configuration` may not push settings.
+        match self.configuration_support().pull {
+            true => self.pull_settings().await,
+            false => self.apply_settings(settings, "settings").await,
+        }
+    }
+
     async fn did_open(
         &self,
         DidOpenTextDocumentParams {
-            text_document: TextDocumentItem {
-                uri, version, text, ..
-            },
+            text_document:
+                TextDocumentItem {
+                    uri,
+                    language_id,
+                    version,
+                    text,
+                },
         }: DidOpenTextDocumentParams,
     ) {
-        info!(uri = uri.path(), "Opened.");
-        self.on_change(TextItem { uri, text, version }).await;
+        info!(uri = uri.path(), language_id, "Opened.");
+        let item = TextItem {
+            uri,
+            language_id,
+            text,
+            version,
+        };
+        self.on_change(item).await;
     }
 
     async fn did_change(
         &self,
         DidChangeTextDocumentParams {
-            text_document: VersionedTextDocumentIdentifier { uri, version },
-            mut content_changes,
+            text_document,
+            content_changes,
         }: DidChangeTextDocumentParams,
     ) {
-        info!(uri = uri.path(), "Changed.");
-        debug_assert_eq!(content_changes.len(), 1, "We only take full changes.");
-        // TODO: Handle partial changes in `range`.
-        let TextDocumentContentChangeEvent { text, .. } = content_changes.pop().unwrap();
-        self.on_change(TextItem { uri, text, version }).await
+        info!(uri = text_document.uri.path(), "Changed.");
+        self.document_registry
+            .cast(DocumentInfo::Changes(text_document, content_changes))
+            .await
+            .unwrap();
     }
 
     async fn did_close(
@@ -170,13 +331,69 @@ impl LanguageServer for POSLS {
         }: SemanticTokensParams,
     ) -> JsonRes<Option<SemanticTokensResult>> {
         info!(uri = uri.path(), "Full semantic tokens requested.");
-        let maybe_data = self.document_registry.call(uri).await;
-        Ok(maybe_data.ok().map(|data| {
+        let request = TokensRequest {
+            uri,
+            previous_result_id: None,
+            range: None,
+        };
+        let maybe_result = self.document_registry.call(request).await;
+        Ok(maybe_result.ok().map(|result| {
             info!("Sending full semantic tokens.");
-            SemanticTokensResult::Tokens(SemanticTokens {
-                result_id: None,
-                data,
-            })
+            match result {
+                SemanticTokensFullDeltaResult::Tokens(tokens) => {
+                    SemanticTokensResult::Tokens(tokens)
+                }
+                _ => unreachable!("Requests without previous result get full tokens."),
+            }
+        }))
+    }
+
+    async fn semantic_tokens_full_delta(
+        &self,
+        SemanticTokensDeltaParams {
+            text_document: TextDocumentIdentifier { uri },
+            previous_result_id,
+            ..
+        }: SemanticTokensDeltaParams,
+    ) -> JsonRes<Option<SemanticTokensFullDeltaResult>> {
+        info!(
+            uri = uri.path(),
+            previous_result_id, "Semantic token delta requested."
+        );
+        let request = TokensRequest {
+            uri,
+            previous_result_id: Some(previous_result_id),
+            range: None,
+        };
+        let maybe_result = self.document_registry.call(request).await;
+        Ok(maybe_result
+            .ok()
+            .inspect(|_| info!("Sending semantic token delta.")))
+    }
+
+    async fn semantic_tokens_range(
+        &self,
+        SemanticTokensRangeParams {
+            text_document: TextDocumentIdentifier { uri },
+            range,
+            ..
+        }: SemanticTokensRangeParams,
+    ) -> JsonRes<Option<SemanticTokensRangeResult>> {
+        info!(uri = uri.path(), ?range, "Range semantic tokens requested.");
+        let request = TokensRequest {
+            uri,
+            previous_result_id: None,
+            range: Some(range),
+        };
+        let maybe_result = self.document_registry.call(request).await;
+        Ok(maybe_result.ok().map(|result| {
+            info!("Sending range semantic tokens.");
+            match result {
+                SemanticTokensFullDeltaResult::Tokens(tokens) => {
+                    SemanticTokensRangeResult::Tokens(tokens)
+                }
+                _ => unreachable!("Range requests get full tokens."),
+            }
         }))
     }
 
@@ -185,11 +402,6 @@ impl LanguageServer for POSLS {
     }
 }
 
-#[derive(Deserialize)]
-struct InitializationOptions {
-    token_map_update: Option<HashMap<PartOfSpeech, Option<TokenTypeNModifiers>>>,
-}
-
 fn semantic_tokens(
     text: &Rope,
     tokens: &[POSToken],
@@ -199,7 +411,7 @@ fn semantic_tokens(
     let mut slice = text.slice(..);
     tokens
         .iter()
-        .filter_map(|token| token_map.get(token.tag).map(|bits| (token, bits)))
+        .filter_map(|token| token_map.token_bits(token).map(|bits| (token, bits)))
         .map(
             |(
                 token,
@@ -234,22 +446,24 @@ fn semantic_tokens(
 #[derive(Clone, Debug)]
 struct TextItem {
     uri: Url,
+    language_id: String,
     text: String,
     version: i32,
 }
 
 fn server_capabilities() -> ServerCapabilities {
     ServerCapabilities {
-        // TODO: Implement incremental change.
-        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::INCREMENTAL,
+        )),
         semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
             SemanticTokensOptions {
                 legend: SemanticTokensLegend {
                     token_types: semantic_token_types(),
                     token_modifiers: semantic_token_modifiers(),
                 },
-                full: Some(SemanticTokensFullOptions::Bool(true)),
-                // NOTE: Neovim does not support `range`, so we do not either.
+                full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                range: Some(true),
                 ..Default::default()
             },
         )),
@@ -263,6 +477,8 @@ pub struct Document {
     text: Rope,
     tokens: Vec<POSToken>,
     version: i32,
+    /// Tokens of each paragraph, to reuse when re-tagging.
+    chunks: ChunkCache,
 }
 
 #[cfg(test)]
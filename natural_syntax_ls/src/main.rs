@@ -1,7 +1,9 @@
-use std::io::stderr;
+use std::{env::args, io::stderr};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use natural_syntax::POSModelBuilder;
 use natural_syntax_ls::run_part_of_speech_ls;
+use tracing::warn;
 use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
@@ -11,5 +13,32 @@ async fn main() -> Result<()> {
         .with_writer(stderr)
         .with_env_filter(EnvFilter::from_default_env())
         .init();
-    run_part_of_speech_ls().await
+    run_part_of_speech_ls(model_builder()?).await
+}
+
+/// Parse `--model-dir <DIR>` to load a local model instead of downloading it,
+/// and `--weights`, `--config` or `--vocab <PATH>` to load single files
+/// from elsewhere.
+/// `--stdio` is the only transport, and other arguments are ignored.
+fn model_builder() -> Result<POSModelBuilder> {
+    let mut builder = POSModelBuilder::default();
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        let set_path: fn(POSModelBuilder, String) -> POSModelBuilder = match arg.as_str() {
+            "--model-dir" => POSModelBuilder::model_dir,
+            "--weights" => POSModelBuilder::weights,
+            "--config" => POSModelBuilder::config,
+            "--vocab" => POSModelBuilder::vocab,
+            "--stdio" => continue,
+            _ => {
+                warn!(arg, "Ignoring unknown argument.");
+                continue;
+            }
+        };
+        let path = args
+            .next()
+            .with_context(|| format!("`{arg}` needs a path."))?;
+        builder = set_path(builder, path);
+    }
+    Ok(builder)
 }
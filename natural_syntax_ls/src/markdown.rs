@@ -0,0 +1,45 @@
+use std::ops;
+
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+
+use super::*;
+
+/// Byte ranges of the prose in Markdown `text`:
+/// the text of paragraphs, headings, list items, tables and emphasis,
+/// without code, URLs, HTML, math or front matter.
+pub fn markdown_prose(text: &str) -> Vec<ops::Range<usize>> {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_MATH
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+        | Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS;
+    let mut spans = Vec::new();
+    // Depth of code blocks, HTML blocks, front matter and autolinks.
+    let mut n_skipping = 0_usize;
+    for (event, range) in Parser::new_ext(text, options).into_offset_iter() {
+        match event {
+            Event::Start(
+                Tag::CodeBlock(_)
+                | Tag::HtmlBlock
+                | Tag::MetadataBlock(_)
+                | Tag::Link {
+                    link_type: LinkType::Autolink | LinkType::Email,
+                    ..
+                },
+            ) => n_skipping += 1,
+            Event::End(TagEnd::CodeBlock | TagEnd::HtmlBlock | TagEnd::MetadataBlock(_)) => {
+                n_skipping = n_skipping.saturating_sub(1)
+            }
+            Event::End(TagEnd::Link) if n_skipping > 0 => n_skipping -= 1,
+            Event::Text(_) if n_skipping == 0 => push_without_urls(text, range, &mut spans),
+            Event::Start(Tag::TableCell) | Event::HardBreak => push_break(range.start, &mut spans),
+            Event::InlineHtml(html) if html.to_ascii_lowercase().starts_with("<br") => {
+                push_break(range.start, &mut spans)
+            }
+            _ => {}
+        }
+    }
+    spans
+}
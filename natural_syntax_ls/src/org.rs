@@ -0,0 +1,207 @@
+use std::ops;
+
+use super::*;
+
+/// Blocks whose content is not prose, by lowercase block type.
+const SKIPPED_BLOCKS: &[&str] = &["comment", "example", "export", "src"];
+
+/// Keywords whose values are prose, in lowercase.
+const PROSE_KEYWORDS: &[&str] = &["caption", "subtitle", "title"];
+
+/// Keywords starting headlines after the stars.
+const HEADLINE_KEYWORDS: &[&str] = &["COMMENT", "DONE", "TODO"];
+
+/// Byte ranges of the prose in Org `text`:
+/// headlines, paragraphs, list items, table cells, quotes and titles,
+/// without markup, comments, drawers, source or example blocks, verbatim,
+/// link targets, math or timestamps.
+pub fn org_prose(text: &str) -> Vec<ops::Range<usize>> {
+    let mut spans = Vec::new();
+    // Lowercase line ending the block or drawer being skipped.
+    let mut skipping_until: Option<String> = None;
+    for (start, line) in lines_with_offsets(text) {
+        let content = line.trim_end();
+        let trimmed = content.trim_start();
+        let end = start + content.len();
+        let lowercase = trimmed.to_ascii_lowercase();
+        if let Some(block_end) = &skipping_until {
+            if lowercase.starts_with(block_end.as_str()) {
+                skipping_until = None;
+            }
+            continue;
+        }
+        if let Some(block) = lowercase.strip_prefix("#+begin_") {
+            let block_type = block.split_whitespace().next().unwrap_or_default();
+            if SKIPPED_BLOCKS.contains(&block_type) {
+                skipping_until = Some(format!("#+end_{block_type}"));
+            }
+            continue;
+        }
+        // Keywords, and the end lines of other blocks.
+        if let Some(keyword) = lowercase.strip_prefix("#+") {
+            if let Some((key, value)) = keyword.split_once(':') {
+                if PROSE_KEYWORDS.contains(&key) {
+                    push_inline(text, end - value.trim_start().len()..end, &mut spans);
+                }
+            }
+            continue;
+        }
+        let is_drawer = trimmed.len() > 2
+            && trimmed.starts_with(':')
+            && trimmed.ends_with(':')
+            && trimmed[1..trimmed.len() - 1]
+                .chars()
+                .all(|char| char.is_ascii_alphanumeric() || char == '_' || char == '-');
+        if is_drawer {
+            skipping_until = Some(":end:".into());
+            continue;
+        }
+        // Comments, fixed-width lines, planning lines and table rules.
+        if trimmed.is_empty()
+            || trimmed == "#"
+            || trimmed.starts_with("# ")
+            || trimmed == ":"
+            || trimmed.starts_with(": ")
+            || ["SCHEDULED:", "DEADLINE:", "CLOSED:"]
+                .iter()
+                .any(|planning| trimmed.starts_with(planning))
+            || trimmed.starts_with("|-")
+        {
+            continue;
+        }
+        if trimmed.starts_with('|') {
+            let mut cell_start = end - trimmed.len();
+            for cell in trimmed.split('|') {
+                push_break(cell_start, &mut spans);
+                push_inline(text, cell_start..cell_start + cell.len(), &mut spans);
+                cell_start += cell.len() + 1;
+            }
+            continue;
+        }
+        let is_headline =
+            content.starts_with('*') && content.trim_start_matches('*').starts_with(' ');
+        let (item, end) = match is_headline {
+            true => {
+                let headline = without_tags(content);
+                (headline_title(headline), start + headline.len())
+            }
+            false => (list_item_text(trimmed), end),
+        };
+        push_inline(text, end - item.len()..end, &mut spans);
+    }
+    spans
+}
+
+/// Title of `headline` without its stars, keyword or priority.
+fn headline_title(headline: &str) -> &str {
+    let mut title = headline.trim_start_matches('*').trim_start();
+    if let Some((keyword, rest)) = title.split_once(' ') {
+        if HEADLINE_KEYWORDS.contains(&keyword) {
+            title = rest.trim_start();
+        }
+    }
+    if let Some(rest) = title.strip_prefix("[#") {
+        title = rest
+            .split_once(']')
+            .map_or(title, |(_, rest)| rest.trim_start());
+    }
+    title
+}
+
+/// `headline` without its trailing tags.
+fn without_tags(headline: &str) -> &str {
+    match headline.rsplit_once(char::is_whitespace) {
+        Some((title, tags)) if tags.len() > 1 && tags.starts_with(':') && tags.ends_with(':') => {
+            title.trim_end()
+        }
+        _ => headline,
+    }
+}
+
+/// `line` without its bullet, counter or checkbox.
+fn list_item_text(line: &str) -> &str {
+    let Some((marker, item)) = line.split_once(' ') else {
+        return line;
+    };
+    let is_bullet = matches!(marker, "-" | "+" | "*");
+    let is_counter = marker.strip_suffix(['.', ')']).is_some_and(|counter| {
+        (!counter.is_empty() && counter.chars().all(|char| char.is_ascii_digit()))
+            || (counter.len() == 1 && counter.chars().all(|char| char.is_ascii_alphabetic()))
+    });
+    if !is_bullet && !is_counter {
+        return line;
+    }
+    let item = item.trim_start();
+    ["[ ] ", "[X] ", "[x] ", "[-] "]
+        .iter()
+        .find_map(|checkbox| item.strip_prefix(checkbox))
+        .unwrap_or(item)
+}
+
+/// Push the prose in `range` of `text` to `spans`, cutting out verbatim,
+/// code, link targets, math, entities, timestamps, cookies and URLs.
+fn push_inline(text: &str, range: ops::Range<usize>, spans: &mut Vec<ops::Range<usize>>) {
+    let (mut start, mut index) = (range.start, range.start);
+    while let Some(offset) = text[index..range.end].find(['=', '~', '[', '<', '$', '\\']) {
+        let markup = index + offset;
+        let rest = &text[markup..range.end];
+        let closing = |open: usize, close: &str| {
+            rest[open..]
+                .find(close)
+                .map(|offset| markup + open + offset + close.len())
+        };
+        let starts_with_digit = rest[1..].starts_with(|char: char| char.is_ascii_digit());
+        let (prose, markup_end) = if rest.starts_with("[[") {
+            // Links keep their description.
+            let link_end = closing(2, "]]");
+            let description = rest
+                .find("][")
+                .filter(|&offset| link_end.is_some_and(|end| markup + offset < end))
+                .map(|offset| markup + offset + 2..link_end.unwrap_or_default() - 2);
+            (description, link_end)
+        } else if rest.starts_with('=') || rest.starts_with('~') {
+            let marker = &rest[..1];
+            let before = text[range.start..markup].chars().next_back();
+            let opens = before.is_none_or(|char| char.is_whitespace() || "({'\"-".contains(char))
+                && !rest[1..].starts_with(char::is_whitespace);
+            let end = closing(1, marker).filter(|&end| {
+                !text[..end - 1].ends_with(char::is_whitespace)
+                    && !text[end..range.end].starts_with(char::is_alphanumeric)
+            });
+            (None, end.filter(|_| opens))
+        } else if rest.starts_with('[') && (starts_with_digit || rest.starts_with("[fn:")) {
+            // Timestamps, statistics cookies and footnote references.
+            (None, closing(1, "]"))
+        } else if rest.starts_with('<') && starts_with_digit {
+            (None, closing(1, ">"))
+        } else if rest.starts_with("$$") {
+            (None, closing(2, "$$"))
+        } else if rest.starts_with('$') && !rest[1..].starts_with(char::is_whitespace) {
+            let end =
+                closing(1, "$").filter(|&end| !text[..end - 1].ends_with(char::is_whitespace));
+            (None, end)
+        } else if rest.starts_with("\\(") {
+            (None, closing(2, "\\)"))
+        } else if rest.starts_with("\\[") {
+            (None, closing(2, "\\]"))
+        } else if let Some(entity) = rest.strip_prefix('\\') {
+            // Entities and line breaks.
+            let name_len = entity
+                .find(|char: char| !char.is_ascii_alphabetic() && char != '\\')
+                .unwrap_or(entity.len());
+            (None, Some(markup + 1 + name_len).filter(|_| name_len > 0))
+        } else {
+            (None, None)
+        };
+        let Some(markup_end) = markup_end else {
+            index = markup + rest.chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+        push_without_urls(text, start..markup, spans);
+        if let Some(prose) = prose {
+            push_without_urls(text, prose, spans);
+        }
+        (start, index) = (markup_end, markup_end);
+    }
+    push_without_urls(text, start..range.end, spans);
+}
@@ -0,0 +1,221 @@
+use std::{fmt, iter, ops};
+
+use super::*;
+
+/// Extractor of the prose in documents of a markup language,
+/// so only the prose is tagged and highlighted.
+pub trait ProseExtractor: Send + Sync {
+    /// Byte ranges of the prose in `text`, in order and not overlapping.
+    /// Empty ranges mark markup that breaks words, such as `<br>`,
+    /// see [`push_break`]; other spans on the same line are joined.
+    fn prose_spans(&self, text: &str) -> Vec<ops::Range<usize>>;
+}
+
+impl<F> ProseExtractor for F
+where
+    F: Fn(&str) -> Vec<ops::Range<usize>> + Send + Sync,
+{
+    fn prose_spans(&self, text: &str) -> Vec<ops::Range<usize>> {
+        self(text)
+    }
+}
+
+/// [`ProseExtractor`]s by language ID.
+/// Documents of other languages are prose all along.
+#[derive(Clone)]
+pub struct ProseExtractors(HashMap<String, Arc<dyn ProseExtractor>>);
+
+impl ProseExtractors {
+    /// No extractors, not even the built-in ones.
+    pub fn empty() -> Self {
+        Self(HashMap::new())
+    }
+
+    /// Extract the prose of documents of `language_id` with `extractor`.
+    pub fn insert(
+        &mut self,
+        language_id: impl Into<String>,
+        extractor: impl ProseExtractor + 'static,
+    ) {
+        self.0.insert(language_id.into(), Arc::new(extractor));
+    }
+
+    pub fn get(&self, language_id: &str) -> Option<&dyn ProseExtractor> {
+        self.0.get(language_id).map(|extractor| &**extractor)
+    }
+}
+
+impl Default for ProseExtractors {
+    /// The built-in extractors for Markdown, LaTeX, reStructuredText and Org,
+    /// for Git commit messages,
+    /// and for the comments of Rust, Python, JavaScript and TypeScript.
+    fn default() -> Self {
+        let mut extractors = Self::empty();
+        extractors.insert("markdown", markdown_prose);
+        for language_id in ["latex", "tex", "plaintex"] {
+            extractors.insert(language_id, latex_prose);
+        }
+        for language_id in ["rst", "restructuredtext"] {
+            extractors.insert(language_id, restructured_text_prose);
+        }
+        extractors.insert("org", org_prose);
+        extractors.insert(GIT_COMMIT, git_commit_prose);
+        extractors.insert("rust", CommentSyntax::Rust);
+        extractors.insert("python", CommentSyntax::Python);
+        for language_id in [
+            "javascript",
+            "javascriptreact",
+            "typescript",
+            "typescriptreact",
+        ] {
+            extractors.insert(language_id, CommentSyntax::JavaScript);
+        }
+        extractors
+    }
+}
+
+impl fmt::Debug for ProseExtractors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+/// Push non-empty `span` to `spans`, merging it with the last span it follows.
+pub fn push_span(span: ops::Range<usize>, spans: &mut Vec<ops::Range<usize>>) {
+    if span.is_empty() {
+        return;
+    }
+    match spans.last_mut() {
+        Some(last) if last.start < last.end && last.end == span.start => last.end = span.end,
+        _ => spans.push(span),
+    }
+}
+
+/// Mark that the markup at byte `index` breaks the words around it,
+/// as `<br>` does, with an empty span.
+pub fn push_break(index: usize, spans: &mut Vec<ops::Range<usize>>) {
+    if spans.last().is_some_and(|last| !last.is_empty()) {
+        spans.push(index..index);
+    }
+}
+
+/// Lines of `text` without line endings, with the byte offsets they start at.
+pub fn lines_with_offsets(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split_inclusive('\n').scan(0, |start, line| {
+        let line_start = *start;
+        *start += line.len();
+        Some((line_start, line.trim_end_matches(['\n', '\r'])))
+    })
+}
+
+/// Push `range` of `text` to `spans`, cutting out bare URLs.
+pub fn push_without_urls(text: &str, range: ops::Range<usize>, spans: &mut Vec<ops::Range<usize>>) {
+    let (mut start, mut word_start) = (range.start, range.start);
+    for piece in text[range.clone()].split_inclusive(char::is_whitespace) {
+        let word = piece.trim_end();
+        if ["http://", "https://", "www.", "mailto:"]
+            .iter()
+            .any(|prefix| word.starts_with(prefix))
+        {
+            push_span(start..word_start, spans);
+            start = word_start + word.len();
+        }
+        word_start += piece.len();
+    }
+    push_span(start..range.end, spans);
+}
+
+/// Prose extracted from a source document, to tag on its own.
+#[derive(Debug)]
+pub struct Prose {
+    /// The prose spans, joined by up to two line breaks between them,
+    /// or a space where markup breaks words.
+    pub text: String,
+    /// Char offsets where each span starts in `text` and in the source.
+    starts: Vec<(u32, u32)>,
+}
+
+impl Prose {
+    /// Join `spans`, byte ranges of `source` in order.
+    pub fn new(source: &str, spans: &[ops::Range<usize>]) -> Self {
+        let (mut text, mut starts) = (String::new(), Vec::with_capacity(spans.len()));
+        let (mut n_chars, mut n_source_chars, mut source_end) = (0, 0, 0);
+        let (mut n_line_breaks, mut breaks_words) = (0, false);
+        for span in spans {
+            debug_assert!(source_end <= span.start, "Prose spans out of order.");
+            let gap = &source[source_end..span.start];
+            let span_text = &source[span.clone()];
+            source_end = span.end;
+            n_source_chars += gap.chars().count();
+            n_line_breaks += gap.matches('\n').count();
+            if span.is_empty() {
+                breaks_words = true;
+                continue;
+            }
+            let span_chars = span_text.chars().count();
+            // Blank lines only separate the spans around them.
+            if span_text.trim().is_empty() && span_text.contains('\n') {
+                n_line_breaks += span_text.matches('\n').count();
+                n_source_chars += span_chars;
+                continue;
+            }
+            // Keep paragraphs apart, but not before the first span.
+            if !starts.is_empty() {
+                let n_separators = n_line_breaks.min(2);
+                text.extend(iter::repeat_n('\n', n_separators));
+                n_chars += n_separators;
+                let separated = text.ends_with(char::is_whitespace)
+                    || span_text.starts_with(char::is_whitespace);
+                if breaks_words && !separated {
+                    text.push(' ');
+                    n_chars += 1;
+                }
+            }
+            (n_line_breaks, breaks_words) = (0, false);
+            starts.push((n_chars as u32, n_source_chars as u32));
+            text.push_str(span_text);
+            n_chars += span_chars;
+            n_source_chars += span_chars;
+        }
+        Self { text, starts }
+    }
+
+    /// Move the offsets of `tokens` from `text` to the source.
+    pub fn to_source(&self, tokens: &mut [POSToken]) {
+        for token in tokens {
+            let last_char = token.offset_end.saturating_sub(1).max(token.offset_begin);
+            token.offset_end = self.source_offset(last_char) + token.offset_end - last_char;
+            token.offset_begin = self.source_offset(token.offset_begin);
+        }
+    }
+
+    fn source_offset(&self, offset: u32) -> u32 {
+        let index = self
+            .starts
+            .partition_point(|&(start, _)| start <= offset)
+            .saturating_sub(1);
+        match self.starts.get(index) {
+            Some(&(start, source_start)) => source_start + offset.saturating_sub(start),
+            None => offset,
+        }
+    }
+}
+
+/// Tag the prose of `text` that `extractor` extracts, or all of `text`,
+/// with `model` as [`tag_paragraphs`] does, with offsets relative to `text`.
+pub fn tag_document<T: Tagger + ?Sized>(
+    model: &T,
+    extractor: Option<&dyn ProseExtractor>,
+    text: &str,
+    cache: ChunkCache,
+) -> (Vec<POSToken>, ChunkCache, Vec<Error>) {
+    match extractor {
+        None => tag_paragraphs(model, text, cache),
+        Some(extractor) => {
+            let prose = Prose::new(text, &extractor.prose_spans(text));
+            let (mut tokens, cache, errors) = tag_paragraphs(model, &prose.text, cache);
+            prose.to_source(&mut tokens);
+            (tokens, cache, errors)
+        }
+    }
+}
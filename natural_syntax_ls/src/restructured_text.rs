@@ -0,0 +1,198 @@
+use std::ops;
+
+use super::*;
+
+/// Directives whose arguments and content are prose.
+const PROSE_DIRECTIVES: &[&str] = &[
+    "admonition",
+    "attention",
+    "caution",
+    "danger",
+    "epigraph",
+    "error",
+    "highlights",
+    "hint",
+    "important",
+    "note",
+    "pull-quote",
+    "rubric",
+    "seealso",
+    "sidebar",
+    "tip",
+    "topic",
+    "warning",
+];
+
+/// Byte ranges of the prose in reStructuredText `text`:
+/// paragraphs, section titles, list items, table cells, footnotes and
+/// admonitions, without inline markup, comments, other directives,
+/// literal blocks or URLs.
+pub fn restructured_text_prose(text: &str) -> Vec<ops::Range<usize>> {
+    let mut spans = Vec::new();
+    // Indentation of the line introducing the indented block being skipped.
+    let mut skipping_past = None;
+    for (start, line) in lines_with_offsets(text) {
+        let content = line.trim_end();
+        let trimmed = content.trim_start();
+        let indent = content.len() - trimmed.len();
+        if let Some(block_indent) = skipping_past {
+            if trimmed.is_empty() || indent > block_indent {
+                continue;
+            }
+            skipping_past = None;
+        }
+        let end = start + content.len();
+        match trimmed.strip_prefix("..") {
+            // Explicit markup: directives, comments, targets and footnotes.
+            Some(markup) if markup.is_empty() || markup.starts_with(char::is_whitespace) => {
+                let markup = markup.trim_start();
+                let markup_start = end - markup.len();
+                if let Some((name, argument)) = markup.split_once("::") {
+                    if PROSE_DIRECTIVES.contains(&name.trim()) {
+                        push_inline(text, end - argument.trim_start().len()..end, &mut spans);
+                        continue;
+                    }
+                } else if let Some(label_end) =
+                    markup.strip_prefix('[').and_then(|label| label.find(']'))
+                {
+                    // Footnotes and citations.
+                    push_inline(text, markup_start + label_end + 2..end, &mut spans);
+                    continue;
+                }
+                skipping_past = Some(indent);
+                continue;
+            }
+            _ => {}
+        }
+        // Blank lines, section adornments and table borders.
+        if trimmed
+            .chars()
+            .all(|char| char.is_ascii_punctuation() || char == ' ')
+        {
+            continue;
+        }
+        // Table rows and line blocks.
+        if trimmed.starts_with('|') {
+            let mut cell_start = end - trimmed.len();
+            for cell in trimmed.split('|') {
+                push_break(cell_start, &mut spans);
+                push_inline(text, cell_start..cell_start + cell.len(), &mut spans);
+                cell_start += cell.len() + 1;
+            }
+            continue;
+        }
+        let item = list_item_text(trimmed);
+        let mut prose = end - item.len()..end;
+        // A paragraph ending in `::` introduces a literal block,
+        // and `text::` reads as `text:`.
+        if let Some(before) = item.strip_suffix("::") {
+            prose.end -= match before.ends_with(char::is_whitespace) || before.is_empty() {
+                true => 2,
+                false => 1,
+            };
+            skipping_past = Some(indent);
+        }
+        push_inline(text, prose, &mut spans);
+    }
+    spans
+}
+
+/// `line` without its bullet, enumerator or field marker.
+fn list_item_text(line: &str) -> &str {
+    if let Some(field) = line.strip_prefix(':') {
+        if let Some(name_end) = field.find(':') {
+            let value = &field[name_end + 1..];
+            if name_end > 0 && (value.is_empty() || value.starts_with(char::is_whitespace)) {
+                return value.trim_start();
+            }
+        }
+    }
+    let Some((marker, item)) = line.split_once(char::is_whitespace) else {
+        return line;
+    };
+    let enumerator = marker
+        .strip_suffix(['.', ')'])
+        .map(|enumerator| enumerator.strip_prefix('(').unwrap_or(enumerator));
+    let is_marker = match enumerator {
+        Some(enumerator) => {
+            enumerator == "#"
+                || (!enumerator.is_empty() && enumerator.chars().all(|char| char.is_ascii_digit()))
+                || (enumerator.len() == 1
+                    && enumerator.chars().all(|char| char.is_ascii_alphabetic()))
+                || (!enumerator.is_empty()
+                    && enumerator.chars().all(|char| "ivxIVX".contains(char)))
+        }
+        None => matches!(marker, "-" | "*" | "+" | "•" | "‣" | "⁃"),
+    };
+    match is_marker {
+        true => item.trim_start(),
+        false => line,
+    }
+}
+
+/// Push the prose in `range` of `text` to `spans`, cutting out emphasis
+/// markers, inline literals, roles, substitutions, footnote references,
+/// link targets and URLs.
+fn push_inline(text: &str, range: ops::Range<usize>, spans: &mut Vec<ops::Range<usize>>) {
+    let (mut start, mut index) = (range.start, range.start);
+    while let Some(offset) = text[index..range.end].find(['`', '*', '|', ':', '[']) {
+        let markup = index + offset;
+        let rest = &text[markup..range.end];
+        let closing = |open: usize, close: &str| {
+            rest[open..]
+                .find(close)
+                .map(|offset| markup + open + offset + close.len())
+        };
+        let (prose, markup_end) = if rest.starts_with("``") {
+            (None, closing(2, "``"))
+        } else if rest.starts_with('`') {
+            // Interpreted text and hyperlink references keep their text.
+            let content_end = closing(1, "`").map(|end| end - 1);
+            let prose = content_end.map(|content_end| {
+                let content = &text[markup + 1..content_end];
+                let text_end = match (content.ends_with('>'), content.rfind('<')) {
+                    (true, Some(target)) => content[..target].trim_end().len(),
+                    _ => content.len(),
+                };
+                markup + 1..markup + 1 + text_end
+            });
+            (prose, content_end.map(|end| end + 1))
+        } else if rest.starts_with('*') {
+            (
+                None,
+                Some(markup + rest.len() - rest.trim_start_matches('*').len()),
+            )
+        } else if rest.starts_with('|') {
+            (None, closing(1, "|"))
+        } else if rest.starts_with('[') {
+            // Footnote and citation references.
+            let label_end = closing(1, "]").filter(|&end| text[end..range.end].starts_with('_'));
+            (None, label_end.filter(|_| !rest[1..].starts_with(' ')))
+        } else {
+            // Roles before interpreted text.
+            let role_end = rest[1..].find(":`").map(|offset| offset + 2);
+            let is_role = role_end.is_some_and(|role_end| {
+                rest[1..role_end - 1]
+                    .chars()
+                    .all(|char| char.is_ascii_alphanumeric() || "-_.+:".contains(char))
+            });
+            match role_end.filter(|_| is_role) {
+                Some(role_end) => (None, closing(role_end + 1, "`")),
+                None => (None, None),
+            }
+        };
+        let Some(mut markup_end) = markup_end else {
+            index = markup + 1;
+            continue;
+        };
+        // Skip the underscores of references.
+        markup_end += text[markup_end..range.end].len()
+            - text[markup_end..range.end].trim_start_matches('_').len();
+        push_without_urls(text, start..markup, spans);
+        if let Some(prose) = prose {
+            push_without_urls(text, prose, spans);
+        }
+        (start, index) = (markup_end, markup_end);
+    }
+    push_without_urls(text, start..range.end, spans);
+}
@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+
+use serde::{de, Deserializer};
+
+use super::*;
+
+/// Section of the client configuration holding the [`Settings`].
+pub const SETTINGS_SECTION: &str = "natural_syntax_ls";
+
+/// Key of [`TokenMapUpdate::ByLanguage`] for languages not listed.
+pub const DEFAULT_LANGUAGE: &str = "default";
+
+/// Settings from initialization options or the client configuration.
+/// Settings left out keep their current values.
+#[derive(Debug, Default, Deserialize)]
+pub struct Settings {
+    /// Token map to apply `token_map_update` on top of.
+    pub preset: Option<Preset>,
+    /// Update to the token map of the preset, replacing earlier updates.
+    pub token_map_update: Option<TokenMapUpdate>,
+    /// Tokens scoring at most this are not highlighted.
+    pub score_threshold: Option<f64>,
+    /// Thresholds overriding `score_threshold` for some parts of speech,
+    /// replacing earlier ones.
+    pub pos_score_thresholds: Option<HashMap<PartOfSpeech, f64>>,
+    /// Highlight tokens purely of punctuations, such as `SYM` tokens.
+    pub keep_punctuation: Option<bool>,
+    /// Language IDs of the documents to highlight, or all if unset.
+    pub enabled_languages: Option<HashSet<String>>,
+    /// Report the style of the subject lines of Git commit messages.
+    pub commit_message_diagnostics: Option<bool>,
+}
+
+impl Settings {
+    /// Parse settings from `value`, which may nest them under
+    /// [`SETTINGS_SECTION`], as in `workspace/didChangeConfiguration`.
+    /// `null` means no settings.
+    pub fn from_value(mut value: Value) -> serde_json::Result<Option<Self>> {
+        if let Some(section) = value.get_mut(SETTINGS_SECTION) {
+            value = section.take();
+        }
+        match value {
+            Value::Null => Ok(None),
+            value => serde_json::from_value(value).map(Some),
+        }
+    }
+}
+
+/// Update to the token map of the preset, for all languages or by language ID.
+/// Maps keyed by [`DEFAULT_LANGUAGE`] or without any part of speech as key
+/// are by language ID.
+#[derive(Debug)]
+pub enum TokenMapUpdate {
+    AllLanguages(HashMap<PartOfSpeech, Option<TokenTypeNModifiers>>),
+    /// Updates for each language ID, on top of the update for
+    /// [`DEFAULT_LANGUAGE`], which applies to languages not listed.
+    ByLanguage(HashMap<String, HashMap<PartOfSpeech, Option<TokenTypeNModifiers>>>),
+}
+
+impl<'de> Deserialize<'de> for TokenMapUpdate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let map = HashMap::<String, Value>::deserialize(deserializer)?;
+        let by_language =
+            map.contains_key(DEFAULT_LANGUAGE) || !map.keys().any(|key| parse_pos(key).is_ok());
+        if !by_language {
+            return pos_update(map, "").map(Self::AllLanguages);
+        }
+        map.into_iter()
+            .map(|(language_id, update)| {
+                let update = serde_json::from_value(update).map_err(|err| {
+                    de::Error::custom(format!("`token_map_update.{language_id}`: {err}"))
+                })?;
+                let update = pos_update(update, &format!("{language_id}."))?;
+                Ok((language_id, update))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self::ByLanguage)
+    }
+}
+
+/// Parse the token map `update` under `path` of `token_map_update`,
+/// naming the key that fails to parse.
+fn pos_update<E: de::Error>(
+    update: HashMap<String, Value>,
+    path: &str,
+) -> Result<HashMap<PartOfSpeech, Option<TokenTypeNModifiers>>, E> {
+    update
+        .into_iter()
+        .map(|(key, value)| {
+            let err = |err: serde_json::Error| {
+                E::custom(format!("`token_map_update.{path}{key}`: {err}"))
+            };
+            let pos = parse_pos(&key).map_err(err)?;
+            let token = serde_json::from_value(value).map_err(err)?;
+            Ok((pos, token))
+        })
+        .collect()
+}
+
+fn parse_pos(key: &str) -> serde_json::Result<PartOfSpeech> {
+    serde_json::from_value(Value::String(key.into()))
+}
+
+/// Settings currently applied to highlighting and diagnostics.
+#[derive(Debug, Default)]
+pub struct Highlighting {
+    /// Token maps of languages with their own updates.
+    token_maps: HashMap<String, TokenMap>,
+    /// Token map of the other languages.
+    default_token_map: TokenMap,
+    preset: Preset,
+    token_map_updates: HashMap<String, HashMap<PartOfSpeech, Option<TokenTypeNModifiers>>>,
+    filter: TokenFilter,
+    enabled_languages: Option<HashSet<String>>,
+    commit_message_diagnostics: bool,
+}
+
+impl Highlighting {
+    pub fn apply(&mut self, settings: Settings) {
+        let Settings {
+            preset,
+            token_map_update,
+            score_threshold,
+            pos_score_thresholds,
+            keep_punctuation,
+            enabled_languages,
+            commit_message_diagnostics,
+        } = settings;
+        if let Some(preset) = preset {
+            self.preset = preset;
+        }
+        if let Some(update) = token_map_update {
+            self.token_map_updates = match update {
+                TokenMapUpdate::AllLanguages(update) => [(DEFAULT_LANGUAGE.into(), update)].into(),
+                TokenMapUpdate::ByLanguage(updates) => updates,
+            };
+        }
+        let filter = &mut self.filter;
+        if let Some(score_threshold) = score_threshold {
+            filter.score_threshold = score_threshold;
+        }
+        if let Some(pos_score_thresholds) = pos_score_thresholds {
+            filter.pos_score_thresholds = pos_score_thresholds;
+        }
+        if let Some(keep_punctuation) = keep_punctuation {
+            filter.keep_punctuation = keep_punctuation;
+        }
+        if let Some(enabled_languages) = enabled_languages {
+            self.enabled_languages = Some(enabled_languages);
+        }
+        if let Some(commit_message_diagnostics) = commit_message_diagnostics {
+            self.commit_message_diagnostics = commit_message_diagnostics;
+        }
+        self.build_token_maps();
+    }
+
+    fn build_token_maps(&mut self) {
+        let mut default_token_map = TokenMap::preset(self.preset);
+        default_token_map.filter = self.filter.clone();
+        if let Some(update) = self.token_map_updates.get(DEFAULT_LANGUAGE) {
+            default_token_map.extend(update.clone());
+        }
+        self.token_maps = self
+            .token_map_updates
+            .iter()
+            .filter(|(language_id, _)| *language_id != DEFAULT_LANGUAGE)
+            .map(|(language_id, update)| {
+                let mut token_map = default_token_map.clone();
+                token_map.extend(update.clone());
+                (language_id.clone(), token_map)
+            })
+            .collect();
+        self.default_token_map = default_token_map;
+    }
+
+    /// Token map for documents of `language_id`.
+    pub fn token_map(&self, language_id: &str) -> &TokenMap {
+        self.token_maps
+            .get(language_id)
+            .unwrap_or(&self.default_token_map)
+    }
+
+    /// If documents of `language_id` are highlighted.
+    pub fn enables(&self, language_id: &str) -> bool {
+        self.enabled_languages
+            .as_ref()
+            .is_none_or(|languages| languages.contains(language_id))
+    }
+
+    /// If documents of `language_id` get diagnostics.
+    pub fn diagnoses(&self, language_id: &str) -> bool {
+        self.commit_message_diagnostics && language_id == GIT_COMMIT && self.enables(language_id)
+    }
+}
+
+/// How the client supports configuration.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConfigurationSupport {
+    /// If the client answers `workspace/configuration`.
+    pub pull: bool,
+    /// If `workspace/didChangeConfiguration` can be registered for dynamically.
+    pub dynamic_registration: bool,
+}
+
+impl ConfigurationSupport {
+    pub fn new(capabilities: &ClientCapabilities) -> Self {
+        let workspace = capabilities.workspace.as_ref();
+        Self {
+            pull: workspace
+                .and_then(|workspace| workspace.configuration)
+                .unwrap_or_default(),
+            dynamic_registration: workspace
+                .and_then(|workspace| workspace.did_change_configuration)
+                .and_then(|capability| capability.dynamic_registration)
+                .unwrap_or_default(),
+        }
+    }
+}
@@ -1,8 +1,12 @@
+#[cfg(feature = "rust-bert")]
 use std::time::Instant;
 
 use super::*;
 
 use insta::assert_debug_snapshot;
+#[cfg(feature = "rust-bert")]
+use natural_syntax::POSModel;
+use natural_syntax::{FakeTagger, PartOfSpeechError};
 use tracing::Level;
 use tracing_subscriber::EnvFilter;
 
@@ -22,13 +26,33 @@ fn token_modifiers() {
 
 #[test]
 fn filtering_tokens() {
-    assert!(filter_token(&dummy_token("word", 1.)));
-    assert!(filter_token(&dummy_token("word,", 0.4)));
-    assert!(!filter_token(&dummy_token("word", 0.3)));
-    assert!(!filter_token(&dummy_token(",", 0.3)));
-    assert!(!filter_token(&dummy_token(",", 1.)));
-    assert!(!filter_token(&dummy_token("]", 1.)));
-    assert!(!filter_token(&dummy_token("(", 1.)));
+    let filter = TokenFilter::default();
+    assert!(filter.keeps(&dummy_token("word", 1.)));
+    assert!(filter.keeps(&dummy_token("word,", 0.4)));
+    assert!(!filter.keeps(&dummy_token("word", 0.3)));
+    assert!(!filter.keeps(&dummy_token(",", 0.3)));
+    assert!(!filter.keeps(&dummy_token(",", 1.)));
+    assert!(!filter.keeps(&dummy_token("]", 1.)));
+    assert!(!filter.keeps(&dummy_token("(", 1.)));
+
+    let filter = TokenFilter {
+        score_threshold: 0.9,
+        pos_score_thresholds: [(PartOfSpeech::NN, 0.1)].into(),
+        keep_punctuation: true,
+    };
+    assert!(filter.keeps(&POSToken {
+        tag: PartOfSpeech::NN,
+        ..dummy_token("word", 0.2)
+    }));
+    assert!(!filter.keeps(&POSToken {
+        tag: PartOfSpeech::VB,
+        ..dummy_token("word", 0.8)
+    }));
+    assert!(filter.keeps(&dummy_token(",", 0.95)));
+    assert!(filter.keeps(&POSToken {
+        tag: PartOfSpeech::SYM,
+        ..dummy_token("]", 1.)
+    }));
 }
 
 fn dummy_token(word: &str, score: f64) -> POSToken {
@@ -39,13 +63,7 @@ fn dummy_token(word: &str, score: f64) -> POSToken {
     }
 }
 
-#[test]
-fn convert_tokens() {
-    init_tracing();
-    let start = Instant::now();
-    let model = POSModel::try_default().unwrap();
-    info!("Took {}ms to load the model.", start.elapsed().as_millis());
-    let input = "The easiest way of getting started is the [rustler Elixir library](https://hex.pm/packages/rustler).
+const MARKDOWN: &str = "The easiest way of getting started is the [rustler Elixir library](https://hex.pm/packages/rustler).
 
 - Add the [rustler Elixir library](https://hex.pm/packages/rustler) as a
   dependency of your project.
@@ -55,12 +73,21 @@ fn convert_tokens() {
   `serde_rustler` before, please enable the `serde` feature in your NIF crate's
   `Cargo.toml` on the `rustler` dependency.
 ";
+
+#[cfg(feature = "rust-bert")]
+#[test]
+fn convert_tokens() {
+    init_tracing();
+    let start = Instant::now();
+    let model = POSModel::try_default().unwrap();
+    info!("Took {}ms to load the model.", start.elapsed().as_millis());
+    let input = MARKDOWN;
     let start = Instant::now();
     let output = model.predict(input);
     info!("Took {}ms to predict.", start.elapsed().as_millis());
     let mut tokens = output
         .map(|r| r.unwrap())
-        .filter(filter_token)
+        .filter(|token| TokenFilter::default().keeps(token))
         .collect::<Vec<_>>();
     round_scores(&mut tokens);
     let text = Rope::from_str(input);
@@ -69,8 +96,1062 @@ fn convert_tokens() {
     assert_debug_snapshot!(combined);
 }
 
+#[test]
+fn fake_convert_tokens() {
+    init_tracing();
+    let tokens = fake_tokens(MARKDOWN);
+    let text = Rope::from_str(MARKDOWN);
+    let semantic_tokens = semantic_tokens(&text, &tokens, &Default::default());
+    let combined = tokens.into_iter().zip(semantic_tokens).collect::<Vec<_>>();
+    assert_debug_snapshot!(combined);
+}
+
+#[tokio::test]
+async fn registry_serves_latest_version() {
+    init_tracing();
+    let (_, registry) = DocumentRegistry::new(Arc::new(FakeTagger::default())).spawn();
+    let uri = Url::parse("file:///registry.txt").unwrap();
+    let latest = "The quick brown fox jumps over the lazy dog.";
+    for (version, text) in [(1, "Old text."), (3, latest), (2, "Stale text.")] {
+        let item = TextItem {
+            uri: uri.clone(),
+            language_id: "plaintext".into(),
+            text: text.into(),
+            version,
+        };
+        registry.cast(DocumentInfo::Item(item)).await.unwrap();
+    }
+    wait_for_tokens(&registry, &uri, &fake_semantic_tokens(latest), None).await;
+    let actual = full_tokens(&registry, uri).await;
+    let expected = fake_semantic_tokens(latest);
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn incremental_changes() {
+    let change = |(start_line, start_character), (end_line, end_character), text: &str| {
+        TextDocumentContentChangeEvent {
+            range: Some(Range::new(
+                Position::new(start_line, start_character),
+                Position::new(end_line, end_character),
+            )),
+            range_length: None,
+            text: text.into(),
+        }
+    };
+    let mut text = Rope::from_str("A 😀 cat\r\nsat here.\n");
+    for change in [
+        // After the emoji, two UTF-16 code units long.
+        change((0, 5), (0, 8), "dog"),
+        change((1, 0), (1, 3), "stood"),
+        // Past the end of the line, so clamped before the line break.
+        change((0, 20), (0, 30), "!"),
+        change((1, 6), (2, 0), "there."),
+        change((5, 0), (5, 0), "\nEnd"),
+    ] {
+        apply_change(&mut text, change);
+    }
+    assert_eq!(text.to_string(), "A 😀 dog!\r\nstood there.\nEnd");
+
+    apply_change(
+        &mut text,
+        TextDocumentContentChangeEvent {
+            range: None,
+            range_length: None,
+            text: "Replaced.".into(),
+        },
+    );
+    assert_eq!(text.to_string(), "Replaced.");
+}
+
+#[tokio::test]
+async fn registry_applies_changes_in_order() {
+    init_tracing();
+    let (_, registry) = DocumentRegistry::new(Arc::new(FakeTagger::default())).spawn();
+    let uri = Url::parse("file:///changes.txt").unwrap();
+    let item = TextItem {
+        uri: uri.clone(),
+        language_id: "plaintext".into(),
+        text: "The cat sat.".into(),
+        version: 1,
+    };
+    registry.cast(DocumentInfo::Item(item)).await.unwrap();
+    let changes = |version, text: &str, start, end| {
+        DocumentInfo::Changes(
+            VersionedTextDocumentIdentifier::new(uri.clone(), version),
+            vec![TextDocumentContentChangeEvent {
+                range: Some(Range::new(Position::new(0, start), Position::new(0, end))),
+                range_length: None,
+                text: text.into(),
+            }],
+        )
+    };
+    for info in [
+        changes(2, "dog", 4, 7),
+        // Out of order, so rejected.
+        changes(2, "bird", 4, 7),
+        changes(3, "quietly sat", 8, 11),
+    ] {
+        registry.cast(info).await.unwrap();
+    }
+    let latest = "The dog quietly sat.";
+    wait_for_tokens(&registry, &uri, &fake_semantic_tokens(latest), None).await;
+    let actual = full_tokens(&registry, uri).await;
+    let expected = fake_semantic_tokens(latest);
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn paragraph_retagging() {
+    let tagger = TestTagger::default();
+    // Indexes restart in each paragraph, and are not highlighted anyway.
+    // Tokens are filtered when encoded.
+    let spans = |tokens: Vec<POSToken>| {
+        tokens
+            .into_iter()
+            .filter(|token| TokenFilter::default().keeps(token))
+            .map(|token| (token.word, token.tag, token.offset_begin, token.offset_end))
+            .collect::<Vec<_>>()
+    };
+
+    let paragraphs = ["The cat sat.\n\n", "A dog ran.\n\n", "The cat sat."];
+    let text = paragraphs.concat();
+    let (tokens, cache, errors) = tag_paragraphs(&tagger, &text, ChunkCache::default());
+    assert!(errors.is_empty());
+    assert_eq!(spans(tokens), spans(fake_tokens(&text)));
+    // The repeated paragraph is only tagged once.
+    assert_eq!(tagger.take_inputs(), ["The cat sat.", "A dog ran."]);
+
+    let edited = text.replace("dog ran", "dog quickly ran");
+    let (tokens, _, _) = tag_paragraphs(&tagger, &edited, cache);
+    assert_eq!(spans(tokens), spans(fake_tokens(&edited)));
+    assert_eq!(tagger.take_inputs(), ["A dog quickly ran."]);
+
+    // Failed paragraphs are tagged again, reporting their errors again.
+    let tagger = TestTagger::failing("dog");
+    let (_, cache, errors) = tag_paragraphs(&tagger, &text, ChunkCache::default());
+    assert_eq!(errors.len(), 1);
+    tagger.take_inputs();
+    let (_, _, errors) = tag_paragraphs(&tagger, &text, cache);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(tagger.take_inputs(), ["A dog ran."]);
+}
+
+#[tokio::test]
+async fn semantic_token_deltas() {
+    init_tracing();
+    let (_, registry) = DocumentRegistry::new(Arc::new(FakeTagger::default())).spawn();
+    let uri = Url::parse("file:///delta.txt").unwrap();
+    let old_text = "The cat sat.\n\nA dog ran.\n\nThe end.";
+    let item = TextItem {
+        uri: uri.clone(),
+        language_id: "plaintext".into(),
+        text: old_text.into(),
+        version: 1,
+    };
+    registry.cast(DocumentInfo::Item(item)).await.unwrap();
+    let request = |previous_result_id: Option<&str>| TokensRequest {
+        uri: uri.clone(),
+        previous_result_id: previous_result_id.map(Into::into),
+        range: None,
+    };
+    wait_for_tokens(&registry, &uri, &fake_semantic_tokens(old_text), None).await;
+    let SemanticTokensFullDeltaResult::Tokens(old) = registry.call(request(None)).await.unwrap()
+    else {
+        panic!("Expected full tokens.");
+    };
+
+    let change = DocumentInfo::Changes(
+        VersionedTextDocumentIdentifier::new(uri.clone(), 2),
+        vec![TextDocumentContentChangeEvent {
+            range: Some(Range::new(Position::new(2, 2), Position::new(2, 5))),
+            range_length: None,
+            text: "big dog".into(),
+        }],
+    );
+    registry.cast(change).await.unwrap();
+    let new_text = "The cat sat.\n\nA big dog ran.\n\nThe end.";
+    // The whole document, since full requests would replace the result ID.
+    let whole = Range::new(Position::new(0, 0), Position::new(u32::MAX, 0));
+    wait_for_tokens(
+        &registry,
+        &uri,
+        &fake_semantic_tokens(new_text),
+        Some(whole),
+    )
+    .await;
+    let old_result_id = old.result_id.as_deref();
+    let SemanticTokensFullDeltaResult::TokensDelta(delta) =
+        registry.call(request(old_result_id)).await.unwrap()
+    else {
+        panic!("Expected a delta.");
+    };
+    assert_ne!(delta.result_id.as_deref(), old_result_id);
+    let [edit] = &delta.edits[..] else {
+        panic!("Expected one edit, got {:?}.", delta.edits);
+    };
+    // "big" encodes like the "dog" it shifts, so one token is inserted after it.
+    assert_eq!((edit.start, edit.delete_count), (30, 0));
+    assert_eq!(edit.data.as_ref().map(Vec::len), Some(1));
+    let mut data = old.data;
+    data.splice(
+        (edit.start / 5) as usize..((edit.start + edit.delete_count) / 5) as usize,
+        edit.data.clone().unwrap(),
+    );
+    let expected = fake_semantic_tokens(new_text);
+    assert_eq!(data, expected);
+
+    // Unknown result IDs get full tokens.
+    let result = registry.call(request(Some("unknown"))).await.unwrap();
+    assert!(matches!(result, SemanticTokensFullDeltaResult::Tokens(_)));
+    let result_id = match result {
+        SemanticTokensFullDeltaResult::Tokens(tokens) => tokens.result_id,
+        _ => None,
+    };
+    let result = registry.call(request(result_id.as_deref())).await.unwrap();
+    let SemanticTokensFullDeltaResult::TokensDelta(delta) = result else {
+        panic!("Expected a delta.");
+    };
+    assert!(delta.edits.is_empty());
+}
+
+#[test]
+fn range_tokens() {
+    let text = "The cat sat.\nA dog ran.\nThe end.";
+    let rope = Rope::from_str(text);
+    let tokens = fake_tokens(text);
+    let range = |(start_line, start_character), (end_line, end_character)| {
+        Range::new(
+            Position::new(start_line, start_character),
+            Position::new(end_line, end_character),
+        )
+    };
+    let words = |range| {
+        tokens_in_range(&rope, &tokens, range)
+            .iter()
+            .map(|token| token.word.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(words(range((0, 5), (1, 3))), ["cat", "sat", "A", "dog"]);
+    assert_eq!(words(range((1, 0), (2, 0))), ["A", "dog", "ran"]);
+    assert!(words(range((0, 3), (0, 4))).is_empty());
+    assert_eq!(words(range((2, 4), (9, 0))), ["end"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn range_tagged_first() {
+    init_tracing();
+    // Block on the second paragraph until released.
+    let (tagger, release) = TestTagger::gated("later");
+    let (_, registry) = DocumentRegistry::new(Arc::new(tagger)).spawn();
+    let uri = Url::parse("file:///viewport.txt").unwrap();
+    let text = "The cat sat.\n\nA dog ran later.";
+    let item = TextItem {
+        uri: uri.clone(),
+        language_id: "plaintext".into(),
+        text: text.into(),
+        version: 1,
+    };
+    registry.cast(DocumentInfo::Item(item)).await.unwrap();
+
+    let request = TokensRequest {
+        uri: uri.clone(),
+        previous_result_id: None,
+        range: Some(Range::new(Position::new(0, 0), Position::new(0, 12))),
+    };
+    let SemanticTokensFullDeltaResult::Tokens(visible) = registry.call(request).await.unwrap()
+    else {
+        panic!("Expected tokens.");
+    };
+    let first_paragraph = "The cat sat.";
+    let expected = fake_semantic_tokens(first_paragraph);
+    assert_eq!(visible.data, expected);
+    assert_eq!(visible.result_id, None);
+
+    release.send(()).unwrap();
+    wait_for_tokens(&registry, &uri, &fake_semantic_tokens(text), None).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stale_tokens_served_immediately() {
+    init_tracing();
+    // Block on the edited text until released.
+    let (tagger, release) = TestTagger::gated("slowly");
+    let (_, registry) = DocumentRegistry::new(Arc::new(tagger)).spawn();
+    let uri = Url::parse("file:///stale.txt").unwrap();
+    let (old_text, new_text) = (
+        "The dog ran.\n\nA cat sat.",
+        "The dog ran.\n\nA bird slowly sat.",
+    );
+    let item = TextItem {
+        uri: uri.clone(),
+        language_id: "plaintext".into(),
+        text: old_text.into(),
+        version: 1,
+    };
+    registry.cast(DocumentInfo::Item(item)).await.unwrap();
+    wait_for_tokens(&registry, &uri, &fake_semantic_tokens(old_text), None).await;
+    let change = DocumentInfo::Changes(
+        VersionedTextDocumentIdentifier::new(uri.clone(), 2),
+        vec![TextDocumentContentChangeEvent {
+            range: Some(Range::new(Position::new(2, 2), Position::new(2, 5))),
+            range_length: None,
+            text: "bird slowly".into(),
+        }],
+    );
+    registry.cast(change).await.unwrap();
+
+    // Several concurrent requests are all answered with the stale tokens,
+    // dropping the replaced word and shifting the words after it.
+    let mut stale = fake_tokens(old_text);
+    let edit = CharEdit {
+        range: 16..19,
+        n_inserted: "bird slowly".len(),
+    };
+    remap_tokens(&mut stale, &edit);
+    assert!(stale.iter().all(|token| token.word != "cat"));
+    let stale = semantic_tokens(&Rope::from_str(new_text), &stale, &Default::default());
+    for _ in 0..3 {
+        assert_eq!(full_tokens(&registry, uri.clone()).await, stale);
+    }
+    release.send(()).unwrap();
+    wait_for_tokens(&registry, &uri, &fake_semantic_tokens(new_text), None).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn first_tokens_wait_without_refresh() {
+    init_tracing();
+    let (tagger, release) = TestTagger::gated("slowly");
+    let (_, registry) = DocumentRegistry::new(Arc::new(tagger)).spawn();
+    let uri = Url::parse("file:///first.txt").unwrap();
+    let text = "A bird slowly sat.";
+    let item = TextItem {
+        uri: uri.clone(),
+        language_id: "plaintext".into(),
+        text: text.into(),
+        version: 1,
+    };
+    registry.cast(DocumentInfo::Item(item)).await.unwrap();
+
+    // Without refreshes, the reply waits for the tokens instead of
+    // leaving the client with none.
+    let request = tokio::spawn({
+        let registry = registry.clone();
+        async move { full_tokens(&registry, uri).await }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    assert!(!request.is_finished());
+    release.send(()).unwrap();
+    let expected = fake_semantic_tokens(text);
+    assert_eq!(request.await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn runtime_settings() {
+    init_tracing();
+    let (_, registry) = DocumentRegistry::new(Arc::new(FakeTagger::default())).spawn();
+    let uri = Url::parse("file:///settings.txt").unwrap();
+    let text = "The dog ran home.";
+    let item = TextItem {
+        uri: uri.clone(),
+        language_id: "plaintext".into(),
+        text: text.into(),
+        version: 1,
+    };
+    registry.cast(DocumentInfo::Item(item)).await.unwrap();
+    wait_for_tokens(&registry, &uri, &fake_semantic_tokens(text), None).await;
+    let configure = |json: &str| {
+        let settings = Settings::from_value(serde_json::from_str(json).unwrap());
+        DocumentInfo::Configure(settings.unwrap().unwrap())
+    };
+    assert!(Settings::from_value(Value::Null).unwrap().is_none());
+
+    // Settings pushed by the client are nested under their section.
+    let update = r#"{"natural_syntax_ls":{"token_map_update":{"NN":null}}}"#;
+    registry.cast(configure(update)).await.unwrap();
+    let mut token_map = TokenMap::default();
+    token_map.extend([(PartOfSpeech::NN, None)].into());
+    let expected = semantic_tokens(&Rope::from_str(text), &fake_tokens(text), &token_map);
+    assert_eq!(full_tokens(&registry, uri.clone()).await, expected);
+
+    registry
+        .cast(configure(r#"{"score_threshold":1.0}"#))
+        .await
+        .unwrap();
+    assert_eq!(full_tokens(&registry, uri.clone()).await, []);
+
+    // Documents of disabled languages are neither tagged nor highlighted.
+    let settings =
+        r#"{"token_map_update":{},"score_threshold":0.34,"enabled_languages":["markdown"]}"#;
+    registry.cast(configure(settings)).await.unwrap();
+    let change = DocumentInfo::Changes(
+        VersionedTextDocumentIdentifier::new(uri.clone(), 2),
+        vec![TextDocumentContentChangeEvent {
+            range: Some(Range::new(Position::new(0, 17), Position::new(0, 17))),
+            range_length: None,
+            text: " Then it sat.".into(),
+        }],
+    );
+    registry.cast(change).await.unwrap();
+    assert_eq!(full_tokens(&registry, uri.clone()).await, []);
+
+    let settings = r#"{"enabled_languages":["markdown","plaintext"]}"#;
+    registry.cast(configure(settings)).await.unwrap();
+    wait_for_tokens(
+        &registry,
+        &uri,
+        &fake_semantic_tokens("The dog ran home. Then it sat."),
+        None,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn language_token_maps() {
+    init_tracing();
+    let (_, registry) = DocumentRegistry::new(Arc::new(FakeTagger::default())).spawn();
+    let text = "The dog ran home.";
+    let settings = r#"{"token_map_update":{"default":{"DT":null},"markdown":{"NN":null}}}"#;
+    let settings = Settings::from_value(serde_json::from_str(settings).unwrap());
+    registry
+        .cast(DocumentInfo::Configure(settings.unwrap().unwrap()))
+        .await
+        .unwrap();
+
+    for (language_id, hidden) in [
+        ("plaintext", &[PartOfSpeech::DT][..]),
+        ("markdown", &[PartOfSpeech::DT, PartOfSpeech::NN]),
+    ] {
+        let uri = Url::parse(&format!("file:///language.{language_id}")).unwrap();
+        let item = TextItem {
+            uri: uri.clone(),
+            language_id: language_id.into(),
+            text: text.into(),
+            version: 1,
+        };
+        registry.cast(DocumentInfo::Item(item)).await.unwrap();
+        let mut token_map = TokenMap::default();
+        token_map.extend(hidden.iter().map(|&pos| (pos, None)).collect());
+        let expected = semantic_tokens(&Rope::from_str(text), &fake_tokens(text), &token_map);
+        wait_for_tokens(&registry, &uri, &expected, None).await;
+    }
+
+    // A map for all languages still parses.
+    let settings = r#"{"token_map_update":{"NN":null}}"#;
+    let settings = Settings::from_value(serde_json::from_str(settings).unwrap());
+    assert!(matches!(
+        settings.unwrap().unwrap().token_map_update,
+        Some(TokenMapUpdate::AllLanguages(_))
+    ));
+
+    // Errors name the key that fails to parse.
+    for (settings, key) in [
+        (r#"{"CC":null,"NNX":null}"#, "`token_map_update.NNX`"),
+        (r#"{"NN":{"type":"nope"}}"#, "`token_map_update.NN`"),
+        (
+            r#"{"default":{"CC":null},"markdown":{"NN":{"type":"nope"}}}"#,
+            "`token_map_update.markdown.NN`",
+        ),
+        (r#"{"markdown":[]}"#, "`token_map_update.markdown`"),
+    ] {
+        let settings = format!(r#"{{"token_map_update":{settings}}}"#);
+        let err = Settings::from_value(serde_json::from_str(&settings).unwrap()).unwrap_err();
+        assert!(err.to_string().contains(key), "{err}");
+    }
+}
+
+const MARKDOWN_WITH_CODE: &str = r#"---
+title: Front matter
+---
+
+# Getting started
+
+Run `cargo build` and see https://example.com or <https://example.org>.
+
+```rust
+let code = "not prose";
+```
+
+<div>Raw HTML.</div>
+
+Math like $x^2$ is **skipped**, but *this* is kept.
+"#;
+
+#[test]
+fn markdown_prose_text() {
+    let prose = Prose::new(MARKDOWN_WITH_CODE, &markdown_prose(MARKDOWN_WITH_CODE));
+    assert_eq!(
+        prose.text,
+        "Getting started\n\nRun  and see  or .\n\nMath like  is skipped, but this is kept."
+    );
+    let prose = Prose::new(MARKDOWN, &markdown_prose(MARKDOWN));
+    assert_eq!(
+        prose.text,
+        "The easiest way of getting started is the rustler Elixir library.
+
+Add the rustler Elixir library as a
+dependency of your project.
+Run  to generate a new NIF in your project. Follow the
+instructions.
+If you are already using  and/or have been using
+ before, please enable the  feature in your NIF crate's
+ on the  dependency."
+    );
+}
+
+const LATEX: &str = r"\documentclass{article}
+\usepackage[utf8]{inputenc}
+\title{On \emph{Tagging} Papers}
+\begin{document}
+\section*{Introduction}\label{sec:intro}
+We tag prose, % not comments
+like \textbf{this} and $x^2$ or \(y\).
+See Figure~\ref{fig:plot} and \href{https://example.com}{our site}.
+\begin{equation}
+    E = mc^2
+\end{equation}
+\begin{figure}[t]
+    \includegraphics[width=\linewidth]{plot.pdf}
+    \caption{A plot of \verb|data| over time.}
+\end{figure}
+\begin{verbatim}
+Not prose.
+\end{verbatim}
+Costs \$5 in total.
+\end{document}
+";
+
+#[test]
+fn latex_prose_text() {
+    let prose = Prose::new(LATEX, &latex_prose(LATEX));
+    assert_eq!(
+        prose.text,
+        "On Tagging Papers
+
+Introduction
+We tag prose, \nlike this and  or .
+See Figure and our site.\n\n\nA plot of  over time.\n\n\nCosts 5 in total.\n"
+    );
+    let mut tokens = fake_tokens(&prose.text);
+    prose.to_source(&mut tokens);
+    for token in tokens {
+        let (begin, end) = (token.offset_begin as usize, token.offset_end as usize);
+        assert_eq!(LATEX[begin..end], token.word);
+    }
+}
+
+const RESTRUCTURED_TEXT: &str = r"=========
+Our Guide
+=========
+
+Read the ``tag`` command docs at `our site <https://example.com>`_
+and :ref:`tagging`, with *emphasis* [#]_.
+
+.. note:: Notes are prose.
+
+   So is their body.
+
+.. code-block:: python
+
+   print('Not prose.')
+
+.. A comment.
+
+- First item::
+
+     not prose
+
+2. Second item
+:Author: Someone
+
++------+-------+
+| Cell | Other |
++------+-------+
+";
+
+#[test]
+fn restructured_text_prose_text() {
+    let prose = Prose::new(
+        RESTRUCTURED_TEXT,
+        &restructured_text_prose(RESTRUCTURED_TEXT),
+    );
+    assert_eq!(
+        prose.text,
+        "Our Guide
+
+Read the  command docs at our site
+and , with emphasis .
+
+Notes are prose.
+
+So is their body.
+
+First item:
+
+Second item
+Someone
+
+ Cell  Other "
+    );
+    assert_source_words(RESTRUCTURED_TEXT, &prose);
+}
+
+const ORG: &str = r"#+TITLE: Org Notes
+#+OPTIONS: toc:nil
+* TODO [#A] Tag the prose :work:
+  SCHEDULED: <2024-01-02 Tue>
+  :PROPERTIES:
+  :ID: not-prose
+  :END:
+Use =verbatim= and ~code~ in [[https://example.com][our site]] at $x^2$.
+# A comment.
+- [X] Done item [1/2]
+#+BEGIN_SRC rust
+fn not_prose() {}
+#+END_SRC
+#+BEGIN_QUOTE
+Quoted prose.
+#+END_QUOTE
+| Cell | Other |
+|------+-------|
+: Fixed width.
+";
+
+#[test]
+fn org_prose_text() {
+    let prose = Prose::new(ORG, &org_prose(ORG));
+    assert_eq!(
+        prose.text,
+        "Org Notes
+
+Tag the prose
+
+Use  and  in our site at .
+
+Done item \n\nQuoted prose.\n\n Cell  Other "
+    );
+    assert_source_words(ORG, &prose);
+}
+
+const RUST: &str = r##"//! Tags prose.
+use std::fmt; // Formatting.
+
+/// Tag the `text` in [`Prose`].
+///
+/// ```
+/// let not_prose = 1;
+/// ```
+fn tag<'a>(text: &'a str) -> char {
+    let url = "https://example.com // not a comment";
+    let raw = r#"/* not either */"#;
+    /* Block comment
+     * over lines. */
+    '"'
+}
+"##;
+
+#[test]
+fn rust_comment_prose_text() {
+    let prose = Prose::new(RUST, &CommentSyntax::Rust.prose_spans(RUST));
+    assert_eq!(
+        prose.text,
+        "Tags prose.\nFormatting.\n\nTag the  in [].\n\nBlock comment\nover lines."
+    );
+    assert_source_words(RUST, &prose);
+}
+
+const PYTHON: &str = r##"#!/usr/bin/env python
+"""Tag the prose."""
+import re  # type: ignore
+
+def tag(text):
+    """Tag `text` in docs.
+
+    >>> tag("not prose")
+    """
+    # Matches words, not 'strings'.
+    return re.findall("# not a comment", text)
+"##;
+
+#[test]
+fn python_comment_prose_text() {
+    let prose = Prose::new(PYTHON, &CommentSyntax::Python.prose_spans(PYTHON));
+    assert_eq!(
+        prose.text,
+        "Tag the prose.\n\nTag  in docs.\n\nMatches words, not 'strings'."
+    );
+    assert_source_words(PYTHON, &prose);
+}
+
+const TYPESCRIPT: &str = r#"/**
+ * Tag the prose.
+ * @param text {string} The text to tag.
+ */
+export function tag(text: string): string {
+  const template = `// not a comment ${text}`;
+  return text.replace("/* not either */", ""); // Remove markers.
+}
+"#;
+
+#[test]
+fn typescript_comment_prose_text() {
+    let prose = Prose::new(
+        TYPESCRIPT,
+        &CommentSyntax::JavaScript.prose_spans(TYPESCRIPT),
+    );
+    assert_eq!(
+        prose.text,
+        "Tag the prose.\n text  The text to tag.\n\nRemove markers."
+    );
+    assert_source_words(TYPESCRIPT, &prose);
+}
+
+const COMMIT_MESSAGE: &str = "Fixed tagging of the long commit message subject lines
+
+See https://example.com for the body.
+Signed-off-by: Someone <someone@example.com>
+# Please enter the commit message for your changes.
+# ------------------------ >8 ------------------------
+diff --git a/README.md b/README.md
+";
+
+#[test]
+fn commit_message_prose_text() {
+    let prose = Prose::new(COMMIT_MESSAGE, &git_commit_prose(COMMIT_MESSAGE));
+    assert_eq!(
+        prose.text,
+        "Fixed tagging of the long commit message subject lines\n\nSee  for the body."
+    );
+    assert_source_words(COMMIT_MESSAGE, &prose);
+}
+
+#[test]
+fn commit_message_diagnostics() {
+    let diagnostics = |tagger: &FakeTagger, message: &str| {
+        let extractor = ProseExtractors::default();
+        let (tokens, _, _) = tag_document(
+            tagger,
+            extractor.get(GIT_COMMIT),
+            message,
+            ChunkCache::default(),
+        );
+        subject_diagnostics(&Rope::from_str(message), &tokens)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.range, diagnostic.message))
+            .collect::<Vec<_>>()
+    };
+    let tagger = FakeTagger::default();
+    let range = |start, end| Range {
+        start: Position::new(0, start),
+        end: Position::new(0, end),
+    };
+    assert_eq!(
+        diagnostics(&tagger, COMMIT_MESSAGE),
+        [
+            (
+                range(0, 5),
+                "Subject line should be in the imperative mood, but `Fixed` is tagged VBD.".into()
+            ),
+            (
+                range(50, 54),
+                "Subject line is 54 characters long, over 50.".into()
+            ),
+        ]
+    );
+    // Scopes before the first word are skipped.
+    let scoped_tagger = FakeTagger::default()
+        .with_word("Fixed", PartOfSpeech::VBD)
+        .with_word("Added", PartOfSpeech::VBD);
+    for (subject, start) in [("[area] Fixed tagging", 7), ("feat(ls): Added tags", 10)] {
+        let word = &subject[start as usize..start as usize + 5];
+        let message =
+            format!("Subject line should be in the imperative mood, but `{word}` is tagged VBD.");
+        assert_eq!(
+            diagnostics(&scoped_tagger, subject),
+            [(range(start, start + 5), message)]
+        );
+    }
+    let tagger = tagger.with_word("Fix", PartOfSpeech::VB);
+    assert_eq!(diagnostics(&tagger, "# Comment.\n[area] Fix tagging\n"), []);
+}
+
+#[test]
+fn separated_prose_text() {
+    let source = "A word<br>next one.";
+    let prose = Prose::new(source, &[0..6, 6..6, 10..19]);
+    assert_eq!(prose.text, "A word next one.");
+    assert_source_words(source, &prose);
+    // Only markup that breaks words separates them.
+    let prose = Prose::new(source, &[0..6, 10..19]);
+    assert_eq!(prose.text, "A wordnext one.");
+    for (source, spans, expected) in [
+        (
+            "A word<br>next one.",
+            markdown_prose as fn(&str) -> _,
+            "A word next one.",
+        ),
+        ("Then \\textbf{x}y and z.", latex_prose, "Then xy and z."),
+        ("Line\\\\next, and~so.", latex_prose, "Line next, and so."),
+        (
+            "Intro\\footnote{Aside}text.",
+            latex_prose,
+            "Intro Aside text.",
+        ),
+        (
+            "Our[[https://example.com][site]]works.",
+            org_prose,
+            "Oursiteworks.",
+        ),
+        ("|One|Two|", org_prose, "One Two"),
+        ("A cell<br>next.", markdown_prose, "A cell next."),
+    ] {
+        let prose = Prose::new(source, &spans(source));
+        assert_eq!(prose.text, expected);
+        assert_source_words(source, &prose);
+    }
+}
+
+/// Assert tokens of `prose` map back to the same words in `source`,
+/// with markup only between the characters of words it joins.
+fn assert_source_words(source: &str, prose: &Prose) {
+    let mut tokens = fake_tokens(&prose.text);
+    prose.to_source(&mut tokens);
+    let source = Rope::from_str(source);
+    for token in tokens {
+        let (begin, end) = (token.offset_begin as usize, token.offset_end as usize);
+        let source_word = source.slice(begin..end).to_string();
+        let mut source_chars = source_word.chars();
+        assert_eq!(source_word.chars().next(), token.word.chars().next());
+        assert_eq!(source_word.chars().last(), token.word.chars().last());
+        assert!(
+            token
+                .word
+                .chars()
+                .all(|char| source_chars.any(|c| c == char)),
+            "`{}` is not in `{source_word}`",
+            token.word
+        );
+    }
+}
+
+#[tokio::test]
+async fn markdown_tokens_in_prose() {
+    init_tracing();
+    let (_, registry) = DocumentRegistry::new(Arc::new(FakeTagger::default())).spawn();
+    let uri = Url::parse("file:///README.md").unwrap();
+    let item = TextItem {
+        uri: uri.clone(),
+        language_id: "markdown".into(),
+        text: MARKDOWN_WITH_CODE.into(),
+        version: 1,
+    };
+    registry.cast(DocumentInfo::Item(item)).await.unwrap();
+
+    let text = Rope::from_str(MARKDOWN_WITH_CODE);
+    let spans = markdown_prose(MARKDOWN_WITH_CODE);
+    let prose = Prose::new(MARKDOWN_WITH_CODE, &spans);
+    let mut tokens = fake_tokens(&prose.text);
+    prose.to_source(&mut tokens);
+    assert!(!tokens.is_empty());
+    for token in &tokens {
+        let (begin, end) = (token.offset_begin as usize, token.offset_end as usize);
+        assert_eq!(text.slice(begin..end), token.word.as_str());
+        let byte_begin = text.char_to_byte(begin);
+        assert!(spans.iter().any(|span| span.contains(&byte_begin)));
+    }
+    let expected = semantic_tokens(&text, &tokens, &Default::default());
+    wait_for_tokens(&registry, &uri, &expected, None).await;
+}
+
+#[tokio::test]
+async fn custom_extractor_tokens() {
+    init_tracing();
+    // Only quoted lines are prose.
+    let quotes = |text: &str| {
+        let mut spans = Vec::new();
+        for (start, line) in lines_with_offsets(text) {
+            if let Some(quote) = line.strip_prefix("> ") {
+                push_span(start + 2..start + 2 + quote.len(), &mut spans);
+            }
+        }
+        spans
+    };
+    let mut extractors = ProseExtractors::empty();
+    extractors.insert("quotes", quotes);
+    let registry =
+        DocumentRegistry::new(Arc::new(FakeTagger::default())).with_extractors(extractors);
+    let (_, registry) = registry.spawn();
+    let uri = Url::parse("file:///quotes.txt").unwrap();
+    let source = "Not prose.\n> Quoted prose.\nNot either.\n";
+    let item = TextItem {
+        uri: uri.clone(),
+        language_id: "quotes".into(),
+        text: source.into(),
+        version: 1,
+    };
+    registry.cast(DocumentInfo::Item(item)).await.unwrap();
+
+    let text = Rope::from_str(source);
+    let prose = Prose::new(source, &quotes(source));
+    assert_eq!(prose.text, "Quoted prose.");
+    let mut tokens = fake_tokens(&prose.text);
+    prose.to_source(&mut tokens);
+    let expected = semantic_tokens(&text, &tokens, &Default::default());
+    assert_eq!(expected[0].delta_line, 1);
+    wait_for_tokens(&registry, &uri, &expected, None).await;
+}
+
+#[test]
+fn tagging_error_messages() {
+    let uri = Url::parse("file:///long.txt").unwrap();
+    let tagger = TestTagger::failing("broken");
+    let errors = ["One broken word.", "Another broken word."]
+        .into_iter()
+        .flat_map(|input| tagger.predict(input))
+        .filter_map(Result::err)
+        .collect::<Vec<_>>();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(
+        error_messages(&uri, &errors),
+        ["Some words in `file:///long.txt` are not highlighted: \
+        Converting a tagged token: Token `broken` without offset is ignored."]
+    );
+    assert!(error_messages(&uri, &[]).is_empty());
+}
+
+async fn full_tokens(
+    registry: &ActorRef<DocumentRegistry<FakeTagger>>,
+    uri: Url,
+) -> Vec<SemanticToken> {
+    let request = TokensRequest {
+        uri,
+        previous_result_id: None,
+        range: None,
+    };
+    match registry.call(request).await.unwrap() {
+        SemanticTokensFullDeltaResult::Tokens(tokens) => tokens.data,
+        result => panic!("Expected full tokens, got {result:?}."),
+    }
+}
+
+/// Wait until the registry answers with `expected` tokens for `uri`,
+/// polling with full requests, or requests for `range` to leave
+/// the result IDs of full requests alone.
+async fn wait_for_tokens(
+    registry: &ActorRef<DocumentRegistry<FakeTagger>>,
+    uri: &Url,
+    expected: &[SemanticToken],
+    range: Option<Range>,
+) {
+    let request = || TokensRequest {
+        uri: uri.clone(),
+        previous_result_id: None,
+        range,
+    };
+    let mut actual = Vec::new();
+    for _ in 0..500 {
+        if let SemanticTokensFullDeltaResult::Tokens(tokens) =
+            registry.call(request()).await.unwrap()
+        {
+            actual = tokens.data;
+        }
+        if actual == expected {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(actual, expected, "Tokens never settled.");
+}
+
+/// [`FakeTagger`] that records the inputs it tags,
+/// blocks on inputs containing its gate word until released,
+/// and fails on inputs containing its failing word.
+#[derive(Default)]
+struct TestTagger {
+    tagger: FakeTagger,
+    inputs: std::sync::Mutex<Vec<String>>,
+    gate: Option<(
+        &'static str,
+        std::sync::Mutex<std::sync::mpsc::Receiver<()>>,
+    )>,
+    failing: Option<&'static str>,
+}
+
+impl TestTagger {
+    /// Tagger blocking on inputs containing `word`,
+    /// and the sender releasing one blocked input per send.
+    fn gated(word: &'static str) -> (Self, std::sync::mpsc::Sender<()>) {
+        let (release, gate) = std::sync::mpsc::channel();
+        let tagger = Self {
+            gate: Some((word, gate.into())),
+            ..Default::default()
+        };
+        (tagger, release)
+    }
+
+    /// Tagger reporting an error on inputs containing `word`.
+    fn failing(word: &'static str) -> Self {
+        Self {
+            failing: Some(word),
+            ..Default::default()
+        }
+    }
+
+    /// Inputs tagged since the last call.
+    fn take_inputs(&self) -> Vec<String> {
+        mem::take(&mut *self.inputs.lock().unwrap())
+    }
+}
+
+impl Tagger for TestTagger {
+    fn predict(&self, input: &str) -> Vec<Result<POSToken, Error>> {
+        self.inputs.lock().unwrap().push(input.into());
+        if let Some((word, gate)) = &self.gate {
+            if input.contains(word) {
+                gate.lock().unwrap().recv().unwrap();
+            }
+        }
+        let mut results = self.tagger.predict(input);
+        if let Some(word) = self.failing.filter(|word| input.contains(word)) {
+            let err = PartOfSpeechError::MissingOffset(word.into());
+            results.push(Err(Error::Token(err)));
+        }
+        results
+    }
+
+    fn labels(&self) -> &natural_syntax::LabelRegistry {
+        self.tagger.labels()
+    }
+
+    fn max_input_length(&self) -> usize {
+        self.tagger.max_input_length()
+    }
+}
+
+/// Semantic tokens of `text` tagged whole by [`FakeTagger`].
+fn fake_semantic_tokens(text: &str) -> Vec<SemanticToken> {
+    semantic_tokens(
+        &Rope::from_str(text),
+        &fake_tokens(text),
+        &Default::default(),
+    )
+}
+
+fn fake_tokens(input: &str) -> Vec<POSToken> {
+    let mut tokens = FakeTagger::default()
+        .predict(input)
+        .into_iter()
+        .map(|r| r.unwrap())
+        .filter(|token| TokenFilter::default().keeps(token))
+        .collect::<Vec<_>>();
+    tokens.sort_by_key(|token| token.offset_begin);
+    tokens
+}
+
+#[cfg(feature = "rust-bert")]
 const PRECISION: f64 = 1e-3;
 
+#[cfg(feature = "rust-bert")]
 fn round_scores(predictions: &mut [POSToken]) {
     predictions
         .iter_mut()
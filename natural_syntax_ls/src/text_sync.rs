@@ -0,0 +1,85 @@
+use std::ops;
+
+use super::*;
+
+/// Edit replacing the chars in `range` with `n_inserted` chars.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CharEdit {
+    pub range: ops::Range<usize>,
+    pub n_inserted: usize,
+}
+
+/// Apply `change` to `text`, replacing all of it if `change` has no range.
+pub fn apply_change(text: &mut Rope, change: TextDocumentContentChangeEvent) -> CharEdit {
+    let range = match change.range {
+        None => 0..text.len_chars(),
+        Some(Range { start, end }) => {
+            let start = position_to_char(text, start);
+            start..position_to_char(text, end).max(start)
+        }
+    };
+    text.remove(range.clone());
+    text.insert(range.start, &change.text);
+    CharEdit {
+        range,
+        n_inserted: change.text.chars().count(),
+    }
+}
+
+/// Shift the offsets of `tokens` to after `edit`,
+/// dropping tokens that `edit` touches.
+pub fn remap_tokens(tokens: &mut Vec<POSToken>, edit: &CharEdit) {
+    let (start, end) = (edit.range.start as u32, edit.range.end as u32);
+    let n_inserted = edit.n_inserted as u32;
+    tokens.retain_mut(|token| {
+        if token.offset_end < start {
+            true
+        } else if token.offset_begin > end {
+            token.offset_begin = token.offset_begin - end + start + n_inserted;
+            token.offset_end = token.offset_end - end + start + n_inserted;
+            true
+        } else {
+            false
+        }
+    });
+}
+
+/// Char index of `position`, whose `character` counts UTF-16 code units.
+/// Positions past the end of a line or the text are clamped to the end.
+pub fn position_to_char(text: &Rope, position: Position) -> usize {
+    let line = position.line as usize;
+    if line >= text.len_lines() {
+        return text.len_chars();
+    }
+    let line_start = text.line_to_char(line);
+    let line_text = text.line(line);
+    let line_end = line_start + line_text.len_chars() - line_ending_len(line_text);
+    let line_start_cu = text.char_to_utf16_cu(line_start);
+    let line_end_cu = text.char_to_utf16_cu(line_end);
+    let cu = (line_start_cu + position.character as usize).min(line_end_cu);
+    text.utf16_cu_to_char(cu)
+}
+
+/// Position of char index `char`, whose `character` counts UTF-16 code units.
+pub fn char_to_position(text: &Rope, char: usize) -> Position {
+    let char = char.min(text.len_chars());
+    let line = text.char_to_line(char);
+    let line_start_cu = text.char_to_utf16_cu(text.line_to_char(line));
+    Position {
+        line: line as u32,
+        character: (text.char_to_utf16_cu(char) - line_start_cu) as u32,
+    }
+}
+
+/// Number of chars in the line ending of `line`.
+fn line_ending_len(line: RopeSlice) -> usize {
+    let n_chars = line.len_chars();
+    match (
+        n_chars.checked_sub(2).map(|i| line.char(i)),
+        n_chars.checked_sub(1).map(|i| line.char(i)),
+    ) {
+        (Some('\r'), Some('\n')) => 2,
+        (_, Some('\n' | '\r')) => 1,
+        _ => 0,
+    }
+}
@@ -1,9 +1,26 @@
 use super::*;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct TokenMap(HashMap<PartOfSpeech, Option<TokenBits>>);
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenMap {
+    bits: HashMap<PartOfSpeech, Option<TokenBits>>,
+    /// Tokens to highlight at all.
+    pub filter: TokenFilter,
+}
 
 impl TokenMap {
+    /// Token map of `preset`, with the default filter.
+    pub fn preset(preset: Preset) -> Self {
+        Self {
+            bits: (0..N_PART_OF_SPEECH)
+                .map(|index| {
+                    let pos = PartOfSpeech::from_u8(index).unwrap();
+                    (pos, preset.token_bits(pos))
+                })
+                .collect(),
+            filter: Default::default(),
+        }
+    }
+
     pub fn extend(&mut self, update: HashMap<PartOfSpeech, Option<TokenTypeNModifiers>>) {
         let extension = update.into_iter().map(|(pos, option)| {
             let token_bits = option.map(
@@ -17,31 +34,128 @@ impl TokenMap {
             );
             (pos, token_bits)
         });
-        self.0.extend(extension);
+        self.bits.extend(extension);
     }
 
     pub fn get(&self, pos: PartOfSpeech) -> Option<TokenBits> {
-        match self.0.get(&pos) {
+        match self.bits.get(&pos) {
             Some(&Some(token_type_n_modifier)) => Some(token_type_n_modifier),
             _ => None,
         }
     }
+
+    /// Token bits to highlight `token` with, if it passes the filter.
+    pub fn token_bits(&self, token: &POSToken) -> Option<TokenBits> {
+        self.get(token.tag).filter(|_| self.filter.keeps(token))
+    }
 }
 
 impl Default for TokenMap {
     fn default() -> Self {
-        Self(
-            (0..N_PART_OF_SPEECH)
-                .map(|index| {
-                    let pos = PartOfSpeech::from_u8(index).unwrap();
-                    (pos, Some(pos2token_bits(pos)))
+        Self::preset(Preset::Full)
+    }
+}
+
+/// Named token maps to start from.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    /// Every part of speech, as per [`pos2token_bits`].
+    #[default]
+    Full,
+    /// Nouns, verbs, adjectives and adverbs, without modifiers.
+    Minimal,
+    /// Verbs, excluding modals.
+    VerbsOnly,
+    /// Nouns, including proper nouns, and verbs.
+    NounsAndVerbs,
+    /// One token type per word class, as per [`UniversalPos`],
+    /// without modifiers, so all forms of a word class look the same.
+    GrammarLearner,
+}
+
+impl Preset {
+    /// Token bits of `pos` in this preset, if highlighted.
+    pub fn token_bits(self, pos: PartOfSpeech) -> Option<TokenBits> {
+        use UniversalPos::*;
+        let bits = pos2token_bits(pos);
+        match (self, pos.coarse()) {
+            (Self::Full, _)
+            | (Self::VerbsOnly, VERB)
+            | (Self::NounsAndVerbs, NOUN | PROPN | VERB) => Some(bits),
+            (Self::Minimal, NOUN | PROPN | VERB | ADJ | ADV) => Some(TokenBits {
+                token_modifiers_bitset: 0,
+                ..bits
+            }),
+            (Self::GrammarLearner, coarse) => {
+                word_class_token_type(coarse).map(|token_type| TokenBits {
+                    token_type: token_type as u32,
+                    token_modifiers_bitset: 0,
                 })
-                .collect(),
-        )
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Token type of each word class in [`Preset::GrammarLearner`].
+const fn word_class_token_type(coarse: UniversalPos) -> Option<TokenType> {
+    use UniversalPos::*;
+    Some(match coarse {
+        ADJ => TokenType::Type,
+        ADP => TokenType::Comment,
+        ADV => TokenType::EnumMember,
+        AUX => TokenType::Macro,
+        CCONJ | SCONJ => TokenType::Keyword,
+        DET => TokenType::String,
+        INTJ => TokenType::Event,
+        NOUN => TokenType::Parameter,
+        NUM => TokenType::Number,
+        PART => TokenType::Operator,
+        PRON => TokenType::Property,
+        PROPN => TokenType::Class,
+        VERB => TokenType::Function,
+        PUNCT | SYM | X => return None,
+    })
+}
+
+/// Which tagged tokens to highlight.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenFilter {
+    /// Tokens scoring at most this are not highlighted,
+    /// unless their part of speech has its own threshold.
+    pub score_threshold: f64,
+    /// Thresholds overriding `score_threshold` for some parts of speech.
+    pub pos_score_thresholds: HashMap<PartOfSpeech, f64>,
+    /// Keep tokens purely of punctuations, which are mostly tagged `SYM`.
+    pub keep_punctuation: bool,
+}
+
+impl TokenFilter {
+    /// Keep tokens with enough score,
+    /// and not purely punctuations unless configured to.
+    pub fn keeps(&self, token: &POSToken) -> bool {
+        let threshold = match self.pos_score_thresholds.get(&token.tag) {
+            Some(&threshold) => threshold,
+            None => self.score_threshold,
+        };
+        token.score > threshold
+            && (self.keep_punctuation
+                || token.word.chars().any(|char| !char.is_ascii_punctuation()))
     }
 }
 
-#[derive(Debug, Deserialize)]
+impl Default for TokenFilter {
+    fn default() -> Self {
+        Self {
+            score_threshold: 1. / 3.,
+            pos_score_thresholds: HashMap::new(),
+            keep_punctuation: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
 pub struct TokenTypeNModifiers {
     pub r#type: TokenType,
     #[serde(default)]
@@ -54,11 +168,47 @@ mod tests {
 
     const TOKEN_MAP_JSON: &str = r#"{"CC":{"type":"modifier","modifiers":["readonly"]},"TO":{"type":"enumMember"},"IN":null}"#;
 
+    #[test]
+    fn presets() {
+        assert_eq!(TokenMap::preset(Preset::Full), TokenMap::default());
+        let verbs_only = TokenMap::preset(Preset::VerbsOnly);
+        assert_eq!(
+            verbs_only.get(PartOfSpeech::VBD),
+            Some(pos2token_bits(PartOfSpeech::VBD))
+        );
+        assert_eq!(verbs_only.get(PartOfSpeech::MD), None);
+        assert_eq!(verbs_only.get(PartOfSpeech::NN), None);
+        let nouns_and_verbs = TokenMap::preset(Preset::NounsAndVerbs);
+        assert!(nouns_and_verbs.get(PartOfSpeech::NNP).is_some());
+        assert_eq!(nouns_and_verbs.get(PartOfSpeech::JJ), None);
+        let minimal = TokenMap::preset(Preset::Minimal);
+        assert_eq!(
+            minimal
+                .get(PartOfSpeech::NNS)
+                .unwrap()
+                .token_modifiers_bitset,
+            0
+        );
+        assert_eq!(minimal.get(PartOfSpeech::DT), None);
+        let grammar_learner = TokenMap::preset(Preset::GrammarLearner);
+        assert_eq!(
+            grammar_learner.get(PartOfSpeech::VBZ),
+            grammar_learner.get(PartOfSpeech::VB)
+        );
+        assert_ne!(
+            grammar_learner.get(PartOfSpeech::NN),
+            grammar_learner.get(PartOfSpeech::VB)
+        );
+        assert_eq!(grammar_learner.get(PartOfSpeech::SYM), None);
+        let preset: Preset = serde_json::from_str(r#""grammar-learner""#).unwrap();
+        assert_eq!(preset, Preset::GrammarLearner);
+    }
+
     #[test]
     fn extend_default() {
         let mut expected = TokenMap::default();
         let mut actual = expected.clone();
-        expected.0.extend([
+        expected.bits.extend([
             (
                 PartOfSpeech::CC,
                 Some(TokenBits {
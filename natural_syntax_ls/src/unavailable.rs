@@ -0,0 +1,37 @@
+use super::*;
+
+/// Run a Language Server that only tells the client why tagging is
+/// unavailable, e.g., because the model failed to load.
+pub async fn run_ls_unavailable(err: Error) -> Result<()> {
+    let report = err.report();
+    let (service, socket) = LspService::build(|client| UnavailableLS { client, report }).finish();
+    Server::new(stdin(), stdout(), socket).serve(service).await;
+    Ok(())
+}
+
+struct UnavailableLS {
+    client: Client,
+    report: String,
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for UnavailableLS {
+    async fn initialize(&self, _: InitializeParams) -> JsonRes<InitializeResult> {
+        Ok(InitializeResult::default())
+    }
+
+    async fn initialized(&self, _: InitializedParams) {
+        let message = format!(
+            "natural-syntax-ls cannot highlight parts of speech: {}.",
+            self.report
+        );
+        self.client
+            .show_message(MessageType::ERROR, message.clone())
+            .await;
+        self.client.log_message(MessageType::ERROR, message).await;
+    }
+
+    async fn shutdown(&self) -> JsonRes<()> {
+        Ok(())
+    }
+}
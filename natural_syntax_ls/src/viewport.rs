@@ -0,0 +1,46 @@
+use super::*;
+
+/// The `tokens`, sorted by offset, that overlap `range` of `text`.
+pub fn tokens_in_range<'a>(text: &Rope, tokens: &'a [POSToken], range: Range) -> &'a [POSToken] {
+    let start = position_to_char(text, range.start) as u32;
+    let end = position_to_char(text, range.end) as u32;
+    let first = tokens.partition_point(|token| token.offset_end <= start);
+    let last = tokens.partition_point(|token| token.offset_begin < end);
+    &tokens[first..last.max(first)]
+}
+
+/// Tag the prose that `extractor` extracts, or all text,
+/// in the lines of `text` that `range` spans with `model`,
+/// and encode the tokens overlapping `range`.
+/// Used to highlight the viewport of documents not yet tagged.
+pub fn tag_range<T: Tagger + ?Sized>(
+    model: &T,
+    extractor: Option<&dyn ProseExtractor>,
+    text: &Rope,
+    range: Range,
+    token_map: &TokenMap,
+) -> Vec<SemanticToken> {
+    let line_char = |line: u32| match line as usize {
+        line if line < text.len_lines() => text.line_to_char(line),
+        _ => text.len_chars(),
+    };
+    let char_begin = line_char(range.start.line);
+    let char_end = line_char(range.end.line.saturating_add(1)).max(char_begin);
+    let lines = text.char_to_byte(char_begin)..text.char_to_byte(char_end);
+    // Extract prose from the whole text for context, such as code fences.
+    let source = text.to_string();
+    let spans = match extractor {
+        Some(extractor) => extractor
+            .prose_spans(&source)
+            .into_iter()
+            .map(|span| span.start.max(lines.start)..span.end.min(lines.end))
+            .filter(|span| !span.is_empty())
+            .collect(),
+        None => vec![lines],
+    };
+    let prose = Prose::new(&source, &spans);
+    // Errors are reported when the whole document is tagged.
+    let (mut tokens, _, _) = tag_paragraphs(model, &prose.text, ChunkCache::default());
+    prose.to_source(&mut tokens);
+    semantic_tokens(text, tokens_in_range(text, &tokens, range), token_map)
+}
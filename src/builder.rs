@@ -0,0 +1,218 @@
+use std::{
+    fmt::{self, Display},
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use rust_bert::{
+    mobilebert::{MobileBertConfigResources, MobileBertModelResources, MobileBertVocabResources},
+    pipelines::{
+        common::{ModelResource, ModelType},
+        token_classification::{LabelAggregationOption, TokenClassificationConfig},
+    },
+    resources::{LocalResource, RemoteResource, ResourceProvider},
+};
+
+use super::*;
+
+/// Builder of [`POSModel`] from local files, without network access.
+///
+/// Set [`POSModelBuilder::model_dir`] to a directory containing
+/// `rust_model.ot`, `config.json` and `vocab.txt`,
+/// or set the path of each file explicitly.
+/// Explicit paths take precedence over the directory.
+/// Without any path, [`POSModelBuilder::build`] downloads MobileBERT.
+///
+/// The labels come from the `id2label` table of the configuration;
+/// see [`LabelRegistry`].
+#[derive(Clone, Debug, Default)]
+pub struct POSModelBuilder {
+    model_dir: Option<PathBuf>,
+    weights: Option<PathBuf>,
+    config: Option<PathBuf>,
+    vocab: Option<PathBuf>,
+    label_aliases: Vec<(String, PartOfSpeech)>,
+}
+
+impl POSModelBuilder {
+    /// Directory to find model files with their default names in.
+    pub fn model_dir(mut self, model_dir: impl Into<PathBuf>) -> Self {
+        self.model_dir = Some(model_dir.into());
+        self
+    }
+
+    /// Path to the Torch weights, by default `rust_model.ot`.
+    pub fn weights(mut self, weights: impl Into<PathBuf>) -> Self {
+        self.weights = Some(weights.into());
+        self
+    }
+
+    /// Path to the model configuration, by default `config.json`.
+    pub fn config(mut self, config: impl Into<PathBuf>) -> Self {
+        self.config = Some(config.into());
+        self
+    }
+
+    /// Path to the tokenizer vocabulary, by default `vocab.txt`.
+    pub fn vocab(mut self, vocab: impl Into<PathBuf>) -> Self {
+        self.vocab = Some(vocab.into());
+        self
+    }
+
+    /// Map the model label `name` onto `tag`,
+    /// for labels that are not Penn Treebank tags.
+    pub fn label_alias(mut self, name: impl Into<String>, tag: PartOfSpeech) -> Self {
+        self.label_aliases.push((name.into(), tag));
+        self
+    }
+
+    /// Check the model files and load the model.
+    pub fn build(self) -> Result<POSModel, Error> {
+        let (weights, config, vocab) = if self.is_remote() {
+            download_mobilebert().map_err(ModelLoadError::Download)?
+        } else {
+            (
+                self.resolve(ModelFile::Weights, &self.weights)?,
+                self.resolve(ModelFile::Config, &self.config)?,
+                self.resolve(ModelFile::Vocab, &self.vocab)?,
+            )
+        };
+        let (max_input_length, labels) = read_config(&config)?;
+        let labels = self
+            .label_aliases
+            .iter()
+            .fold(labels, |labels, (name, tag)| labels.with_alias(name, *tag));
+
+        let classifier = LabelClassifier::new(&weights, &config, &vocab, max_input_length)
+            .map_err(Error::Initialization)?;
+        let config = TokenClassificationConfig::new(
+            ModelType::MobileBert,
+            ModelResource::Torch(Box::new(LocalResource::from(weights))),
+            LocalResource::from(config),
+            LocalResource::from(vocab),
+            None,
+            true,
+            true,
+            None,
+            LabelAggregationOption::First,
+        );
+        let model = TokenClassificationModel::new(config).map_err(Error::Initialization)?;
+        Ok(POSModel {
+            model,
+            classifier,
+            labels,
+            max_input_length,
+        })
+    }
+
+    fn is_remote(&self) -> bool {
+        [&self.model_dir, &self.weights, &self.config, &self.vocab]
+            .iter()
+            .all(|path| path.is_none())
+    }
+
+    fn resolve(
+        &self,
+        file: ModelFile,
+        explicit: &Option<PathBuf>,
+    ) -> Result<PathBuf, ModelLoadError> {
+        let path = match (explicit, &self.model_dir) {
+            (Some(path), _) => path.clone(),
+            (None, Some(model_dir)) => model_dir.join(file.default_name()),
+            (None, None) => return Err(ModelLoadError::Unspecified(file)),
+        };
+        if path.is_file() {
+            Ok(path)
+        } else {
+            Err(ModelLoadError::MissingFile { file, path })
+        }
+    }
+}
+
+/// Paths to the weights, configuration and vocabulary of MobileBERT,
+/// downloaded unless cached.
+fn download_mobilebert() -> Result<(PathBuf, PathBuf, PathBuf), RustBertError> {
+    let local_path = |resource| RemoteResource::from_pretrained(resource).get_local_path();
+    Ok((
+        local_path(MobileBertModelResources::MOBILEBERT_ENGLISH_POS)?,
+        local_path(MobileBertConfigResources::MOBILEBERT_ENGLISH_POS)?,
+        local_path(MobileBertVocabResources::MOBILEBERT_ENGLISH_POS)?,
+    ))
+}
+
+/// Read the maximum sequence length and the labels of the classifier
+/// from the model configuration.
+fn read_config(path: &Path) -> Result<(usize, LabelRegistry), ModelLoadError> {
+    let malformed = |reason: String| ModelLoadError::MalformedConfig {
+        path: path.into(),
+        reason,
+    };
+    let file = File::open(path).map_err(|err| malformed(err.to_string()))?;
+    let config: serde_json::Value =
+        serde_json::from_reader(BufReader::new(file)).map_err(|err| malformed(err.to_string()))?;
+    let id2label = config
+        .get("id2label")
+        .and_then(serde_json::Value::as_object)
+        .ok_or_else(|| malformed("no `id2label` object".into()))?;
+    let id2label = id2label
+        .iter()
+        .map(|(id, label)| match (id.parse(), label.as_str()) {
+            (Ok(label_index), Some(name)) => Ok((label_index, name.to_owned())),
+            _ => Err(malformed(format!("`id2label` maps `{id}` to `{label}`"))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let max_input_length = match config.get("max_position_embeddings") {
+        None => MOBILEBERT_MAX_LENGTH,
+        Some(value) => value
+            .as_u64()
+            .map(|length| length as usize)
+            .ok_or_else(|| malformed(format!("`max_position_embeddings` is `{value}`")))?,
+    };
+    Ok((max_input_length, LabelRegistry::from_id2label(id2label)))
+}
+
+/// Files making up a [`POSModel`].
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ModelFile {
+    /// Torch weights
+    Weights,
+    /// Model configuration
+    Config,
+    /// Tokenizer vocabulary
+    Vocab,
+}
+
+impl ModelFile {
+    /// File name in a model directory.
+    pub const fn default_name(self) -> &'static str {
+        match self {
+            Self::Weights => "rust_model.ot",
+            Self::Config => "config.json",
+            Self::Vocab => "vocab.txt",
+        }
+    }
+}
+
+impl Display for ModelFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Weights => "weights",
+            Self::Config => "configuration",
+            Self::Vocab => "vocabulary",
+        };
+        write!(f, "{name} (`{}`)", self.default_name())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ModelLoadError {
+    #[error("No path to the model {0}; set the model directory or the file path")]
+    Unspecified(ModelFile),
+    #[error("Missing model {file} at `{path}`")]
+    MissingFile { file: ModelFile, path: PathBuf },
+    #[error("Malformed model configuration at `{path}`: {reason}")]
+    MalformedConfig { path: PathBuf, reason: String },
+    #[error("Downloading the model")]
+    Download(#[source] RustBertError),
+}
@@ -0,0 +1,206 @@
+use std::path::Path;
+
+use rust_bert::{
+    mobilebert::{MobileBertConfig, MobileBertForTokenClassification},
+    pipelines::common::{ModelType, TokenizerOption},
+    Config,
+};
+use rust_tokenizers::{Mask, TokenIdsWithOffsets, TokenizedInput};
+use tch::{nn::VarStore, no_grad, Device, Kind, TchError, Tensor};
+
+use super::*;
+
+/// MobileBERT token classifier that exposes the label probabilities,
+/// which Rust-BERT's pipeline keeps private.
+///
+/// Sub-tokens are consolidated into words like the pipeline of [`POSModel`],
+/// labeling each word after its first sub-token.
+pub struct LabelClassifier {
+    model: MobileBertForTokenClassification,
+    tokenizer: TokenizerOption,
+    /// Holds the weights of `model`.
+    var_store: VarStore,
+    max_length: usize,
+}
+
+impl LabelClassifier {
+    /// Load the classifier with its own weights from `weights`.
+    pub fn new(
+        weights: &Path,
+        config: &Path,
+        vocab: &Path,
+        max_length: usize,
+    ) -> Result<Self, RustBertError> {
+        let mut var_store = VarStore::new(Device::cuda_if_available());
+        let config = MobileBertConfig::from_file(config);
+        let model = MobileBertForTokenClassification::new(var_store.root(), &config)?;
+        var_store.load(weights)?;
+        let vocab = vocab.to_str().ok_or_else(|| {
+            RustBertError::InvalidConfigurationError(format!(
+                "Vocabulary path `{}` is not valid UTF-8",
+                vocab.display()
+            ))
+        })?;
+        let tokenizer =
+            TokenizerOption::from_file(ModelType::MobileBert, vocab, None, true, true, None)?;
+        Ok(Self {
+            model,
+            tokenizer,
+            var_store,
+            max_length,
+        })
+    }
+
+    /// Predict the distribution over `labels` of each word of `input`.
+    /// Input longer than the maximum length is classified in consecutive spans.
+    pub fn predict(
+        &self,
+        input: &str,
+        labels: &LabelRegistry,
+    ) -> Result<Vec<TokenDistribution>, Error> {
+        let spans = self.encode(input);
+        if spans.is_empty() {
+            return Ok(Vec::new());
+        }
+        let probabilities = self.forward(&spans)?;
+
+        let chars = input.chars().collect::<Vec<_>>();
+        let mut distributions: Vec<TokenDistribution> = Vec::new();
+        let (mut index, mut word_index) = (0, 0);
+        for (span, span_probabilities) in spans.iter().zip(probabilities) {
+            for (position, &mask) in span.mask.iter().enumerate() {
+                if mask == Mask::Special {
+                    continue;
+                }
+                index += 1;
+                let probabilities = &span_probabilities[position];
+                let (label_index, score) = arg_max(probabilities);
+                let Some(offset) = span.token_offsets[position] else {
+                    let token_id = span.token_ids[position];
+                    let word = self.tokenizer.decode(&[token_id], false, false);
+                    return Err(PartOfSpeechError::MissingOffset(word).into());
+                };
+                let (begin, end) = (offset.begin, offset.end.min(chars.len() as u32));
+                let text = chars[begin as usize..end as usize]
+                    .iter()
+                    .collect::<String>();
+                match distributions.last_mut() {
+                    Some(last) if mask == Mask::Continuation => {
+                        let token = &mut last.token;
+                        token.word.push_str(&text);
+                        token.offset_end = end;
+                        token.score *= if label_index == token.label_index {
+                            score
+                        } else {
+                            1. - score
+                        };
+                    }
+                    _ => {
+                        word_index += 1;
+                        let token = POSToken {
+                            word: text,
+                            score,
+                            tag: labels.tag_at(label_index).unwrap_or(PartOfSpeech::O),
+                            label_index,
+                            index,
+                            word_index,
+                            offset_begin: begin,
+                            offset_end: end,
+                        };
+                        distributions.push(distribution(token, probabilities, labels));
+                    }
+                }
+            }
+        }
+        Ok(distributions)
+    }
+
+    /// Encode `input` in spans of at most the maximum length,
+    /// each with its special tokens.
+    fn encode(&self, input: &str) -> Vec<TokenizedInput> {
+        let tokens = self.tokenizer.tokenize_with_offsets(input);
+        let ids = self.tokenizer.convert_tokens_to_ids(&tokens.tokens);
+        let span_length = self.max_length.saturating_sub(N_SPECIAL_TOKENS).max(1);
+        (0..ids.len())
+            .step_by(span_length)
+            .map(|start| {
+                let end = (start + span_length).min(ids.len());
+                let span = TokenIdsWithOffsets {
+                    ids: ids[start..end].to_vec(),
+                    offsets: tokens.offsets[start..end].to_vec(),
+                    reference_offsets: tokens.reference_offsets[start..end].to_vec(),
+                    masks: tokens.masks[start..end].to_vec(),
+                };
+                self.tokenizer.build_input_with_special_tokens(span, None)
+            })
+            .collect()
+    }
+
+    /// Label probabilities of each sub-token of each of `spans`,
+    /// in one batched forward pass.
+    fn forward(&self, spans: &[TokenizedInput]) -> Result<Vec<Vec<Vec<f64>>>, Error> {
+        let max_len = spans
+            .iter()
+            .map(|span| span.token_ids.len())
+            .max()
+            .unwrap_or(0);
+        let pad_id = self.tokenizer.get_pad_id().unwrap_or(0);
+        let (input_ids, attention_masks): (Vec<_>, Vec<_>) = spans
+            .iter()
+            .map(|span| {
+                let mut input_ids = span.token_ids.clone();
+                input_ids.resize(max_len, pad_id);
+                let mut attention_mask = vec![1i64; span.token_ids.len()];
+                attention_mask.resize(max_len, 0);
+                (
+                    Tensor::from_slice(&input_ids),
+                    Tensor::from_slice(&attention_mask),
+                )
+            })
+            .unzip();
+        let device = self.var_store.device();
+        let input_ids = Tensor::stack(&input_ids, 0).to(device);
+        let attention_masks = Tensor::stack(&attention_masks, 0).to(device);
+        let output = no_grad(|| {
+            self.model.forward_t(
+                Some(&input_ids),
+                None,
+                None,
+                None,
+                Some(&attention_masks),
+                false,
+            )
+        })
+        .map_err(Error::Prediction)?;
+        let probabilities = output.logits.softmax(-1, Kind::Double).to(Device::Cpu);
+        Vec::try_from(probabilities).map_err(|err: TchError| Error::Prediction(err.into()))
+    }
+}
+
+/// Index and probability of the most probable label.
+fn arg_max(probabilities: &[f64]) -> (i64, f64) {
+    probabilities
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map_or((0, 0.), |(index, &probability)| (index as i64, probability))
+}
+
+/// Distribution of `token` over the parts of speech `labels` map
+/// `probabilities` onto, adding up labels of the same part of speech.
+/// Unmapped labels count as [`PartOfSpeech::O`], as in [`POSToken::from_token`].
+fn distribution(
+    token: POSToken,
+    probabilities: &[f64],
+    labels: &LabelRegistry,
+) -> TokenDistribution {
+    let mut by_tag: Vec<(PartOfSpeech, f64)> = Vec::new();
+    for (label_index, &probability) in probabilities.iter().enumerate() {
+        let tag = labels.tag_at(label_index as i64).unwrap_or(PartOfSpeech::O);
+        match by_tag.iter_mut().find(|(listed, _)| *listed == tag) {
+            Some((_, total)) => *total += probability,
+            None => by_tag.push((tag, probability)),
+        }
+    }
+    TokenDistribution::new(token, by_tag)
+}
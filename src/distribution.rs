@@ -0,0 +1,60 @@
+use super::*;
+
+/// [`POSToken`] with the probabilities of the labels it may take.
+#[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct TokenDistribution {
+    /// The token with its most probable tag
+    pub token: POSToken,
+    /// Labels and their probabilities, most probable first
+    pub probabilities: Vec<(PartOfSpeech, f64)>,
+}
+
+impl TokenDistribution {
+    /// Distribution of `probabilities`, sorted most probable first.
+    pub fn new(token: POSToken, mut probabilities: Vec<(PartOfSpeech, f64)>) -> Self {
+        probabilities.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        Self {
+            token,
+            probabilities,
+        }
+    }
+
+    /// The `k` most probable labels.
+    pub fn top_k(&self, k: usize) -> &[(PartOfSpeech, f64)] {
+        &self.probabilities[..k.min(self.probabilities.len())]
+    }
+
+    /// Probability of `tag`, zero if not listed.
+    pub fn probability(&self, tag: PartOfSpeech) -> f64 {
+        self.probabilities
+            .iter()
+            .find(|(label, _)| *label == tag)
+            .map_or(0., |&(_, probability)| probability)
+    }
+
+    /// Probability mass not assigned to any listed label.
+    pub fn residual(&self) -> f64 {
+        let total: f64 = self.probabilities.iter().map(|(_, p)| p).sum();
+        (1. - total).max(0.)
+    }
+
+    /// If the two most probable labels are less than `margin` apart.
+    pub fn is_ambiguous(&self, margin: f64) -> bool {
+        match self.probabilities[..] {
+            [(_, first), (_, second), ..] => first - second < margin,
+            _ => false,
+        }
+    }
+}
+
+impl From<POSToken> for TokenDistribution {
+    /// Distribution that only knows the predicted tag and its score.
+    fn from(token: POSToken) -> Self {
+        let probabilities = vec![(token.tag, token.score)];
+        Self {
+            token,
+            probabilities,
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use super::*;
+
+/// Errors of loading a model and tagging text.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Model files are unspecified, missing, malformed, or failed to download.
+    #[cfg(feature = "rust-bert")]
+    #[error("Loading the model resources")]
+    ModelResource(#[source] ModelLoadError),
+    /// `libtorch` failed to initialize the model from its resources.
+    #[cfg(feature = "rust-bert")]
+    #[error("Initializing the model with libtorch")]
+    Initialization(#[source] RustBertError),
+    /// `libtorch` failed to run the model.
+    #[cfg(feature = "rust-bert")]
+    #[error("Running the model with libtorch")]
+    Prediction(#[source] RustBertError),
+    /// A token could not be converted into a [`POSToken`].
+    #[error("Converting a tagged token")]
+    Token(#[from] PartOfSpeechError),
+    /// The tagger does not expose the probabilities of the labels,
+    /// so [`Tagger::predict_distribution`] is unavailable.
+    #[error("The tagger does not expose label probabilities")]
+    NoDistribution,
+}
+
+impl Error {
+    /// Messages of this error and its sources, separated by `: `.
+    pub fn report(&self) -> String {
+        let mut report = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(err) = source {
+            report.push_str(": ");
+            report.push_str(&err.to_string());
+            source = err.source();
+        }
+        report
+    }
+}
+
+#[cfg(feature = "rust-bert")]
+impl From<ModelLoadError> for Error {
+    fn from(value: ModelLoadError) -> Self {
+        Self::ModelResource(value)
+    }
+}
@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+
+use super::*;
+
+/// Deterministic in-memory [`Tagger`] for tests,
+/// needing neither `libtorch` nor model downloads.
+///
+/// Words are split on whitespace and punctuation,
+/// then tagged from a word table, falling back to suffix rules.
+/// Ambiguous words like "record" have several probable tags;
+/// other tokens spread the probability their tag does not take evenly
+/// among the other labels.
+/// Like Rust-BERT's pipeline, input past [`Tagger::max_input_length`] is
+/// tagged as well.
+#[derive(Clone, Debug)]
+pub struct FakeTagger {
+    /// Lowercase words and their tags.
+    words: HashMap<String, PartOfSpeech>,
+    /// Lowercase ambiguous words and their probable tags.
+    ambiguous_words: HashMap<String, Vec<(PartOfSpeech, f64)>>,
+    labels: LabelRegistry,
+    max_input_length: usize,
+}
+
+/// Score of tags found in the word table.
+const FAKE_KNOWN_SCORE: f64 = 0.99;
+/// Score of tags guessed from suffixes or capitalization.
+const FAKE_GUESSED_SCORE: f64 = 0.75;
+/// Score of words tagged as nouns for lack of any clue.
+const FAKE_FALLBACK_SCORE: f64 = 0.45;
+/// Score of punctuation, tagged as symbols.
+const FAKE_PUNCTUATION_SCORE: f64 = 0.3;
+
+impl FakeTagger {
+    /// Tag `word` (case-insensitive) as `tag`, overriding the built-in table.
+    pub fn with_word(mut self, word: &str, tag: PartOfSpeech) -> Self {
+        self.words.insert(word.to_lowercase(), tag);
+        self
+    }
+
+    /// Tag `word` (case-insensitive) with `probabilities` of its tags,
+    /// overriding the built-in tables.
+    pub fn with_ambiguous_word(
+        mut self,
+        word: &str,
+        probabilities: &[(PartOfSpeech, f64)],
+    ) -> Self {
+        let word = word.to_lowercase();
+        self.words.remove(&word);
+        self.ambiguous_words.insert(word, probabilities.to_vec());
+        self
+    }
+
+    /// Tag at most `max_input_length` tokens in one pass,
+    /// so longer input is windowed by [`Tagger::predict_long`].
+    pub fn with_max_input_length(mut self, max_input_length: usize) -> Self {
+        self.max_input_length = max_input_length;
+        self
+    }
+
+    fn tag_word(
+        &self,
+        word: &str,
+        previous: Option<&str>,
+        sentence_start: bool,
+    ) -> (PartOfSpeech, f64) {
+        let lowercase = word.to_lowercase();
+        if let Some(&tag) = self.words.get(&lowercase) {
+            return (tag, FAKE_KNOWN_SCORE);
+        }
+        let most_probable = self
+            .ambiguous_words
+            .get(&lowercase)
+            .and_then(|probabilities| {
+                probabilities
+                    .iter()
+                    .copied()
+                    .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            });
+        if let Some(tag_n_probability) = most_probable {
+            return tag_n_probability;
+        }
+        if word.chars().all(|char| !char.is_alphanumeric()) {
+            return (PartOfSpeech::SYM, FAKE_PUNCTUATION_SCORE);
+        }
+        if word.chars().any(|char| char.is_ascii_digit()) {
+            return (PartOfSpeech::CD, FAKE_KNOWN_SCORE);
+        }
+        if lowercase == "s" && previous == Some("'") {
+            return (PartOfSpeech::POS, FAKE_KNOWN_SCORE);
+        }
+        if !sentence_start && word.starts_with(char::is_uppercase) {
+            return (PartOfSpeech::NNP, FAKE_GUESSED_SCORE);
+        }
+        SUFFIX_RULES
+            .iter()
+            .find(|(suffix, _)| lowercase.len() > suffix.len() + 2 && lowercase.ends_with(suffix))
+            .map_or((PartOfSpeech::NN, FAKE_FALLBACK_SCORE), |&(_, tag)| {
+                (tag, FAKE_GUESSED_SCORE)
+            })
+    }
+}
+
+impl Default for FakeTagger {
+    fn default() -> Self {
+        Self {
+            words: KNOWN_WORDS
+                .iter()
+                .map(|&(word, tag)| (word.into(), tag))
+                .collect(),
+            ambiguous_words: AMBIGUOUS_WORDS
+                .iter()
+                .map(|(word, probabilities)| (word.to_string(), probabilities.to_vec()))
+                .collect(),
+            labels: LabelRegistry::penn(),
+            max_input_length: 512,
+        }
+    }
+}
+
+impl Tagger for FakeTagger {
+    fn predict(&self, input: &str) -> Vec<Result<POSToken, Error>> {
+        let mut tokens: Vec<POSToken> = Vec::new();
+        let mut sentence_start = true;
+        for (offset_begin, word) in split_words(input) {
+            let offset_end = offset_begin + word.chars().count();
+            let previous = tokens
+                .last()
+                .filter(|token| token.offset_end as usize == offset_begin)
+                .map(|token| token.word.as_str());
+            let (tag, score) = self.tag_word(&word, previous, sentence_start);
+            sentence_start = matches!(word.as_str(), "." | "!" | "?");
+            // Position 0 is reserved for the special token starting the sequence.
+            let index = tokens.len() as u16 + 1;
+            tokens.push(POSToken {
+                word,
+                score,
+                tag,
+                label_index: tag as i64,
+                index,
+                word_index: index,
+                offset_begin: offset_begin as u32,
+                offset_end: offset_end as u32,
+            });
+        }
+        tokens.into_iter().map(Ok).collect()
+    }
+
+    fn predict_distribution(&self, input: &str) -> Result<Vec<TokenDistribution>, Error> {
+        let distribute = |token: POSToken| {
+            if let Some(probabilities) = self.ambiguous_words.get(&token.word.to_lowercase()) {
+                return TokenDistribution::new(token, probabilities.clone());
+            }
+            let tags = self.labels.parts_of_speech();
+            let rest = (1. - token.score) / (tags.len() - 1) as f64;
+            let probabilities = tags
+                .iter()
+                .map(|&tag| (tag, if tag == token.tag { token.score } else { rest }))
+                .collect();
+            TokenDistribution::new(token, probabilities)
+        };
+        self.predict(input)
+            .into_iter()
+            .map(|result| result.map(distribute))
+            .collect()
+    }
+
+    fn labels(&self) -> &LabelRegistry {
+        &self.labels
+    }
+
+    fn max_input_length(&self) -> usize {
+        self.max_input_length
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        split_words(text).len()
+    }
+}
+
+/// Split `input` into runs of alphanumeric characters and
+/// single other non-whitespace characters,
+/// each with its offset in unicode points.
+fn split_words(input: &str) -> Vec<(usize, String)> {
+    let mut words = Vec::new();
+    let mut chars = input.chars().enumerate().peekable();
+    while let Some((offset_begin, char)) = chars.next() {
+        if char.is_whitespace() {
+            continue;
+        }
+        let mut word = String::from(char);
+        if char.is_alphanumeric() {
+            while let Some((_, char)) = chars.next_if(|(_, char)| char.is_alphanumeric()) {
+                word.push(char);
+            }
+        }
+        words.push((offset_begin, word));
+    }
+    words
+}
+
+/// Suffixes checked in order, so longer suffixes come first.
+const SUFFIX_RULES: [(&str, PartOfSpeech); 14] = [
+    ("ness", PartOfSpeech::NN),
+    ("tion", PartOfSpeech::NN),
+    ("ment", PartOfSpeech::NN),
+    ("able", PartOfSpeech::JJ),
+    ("ible", PartOfSpeech::JJ),
+    ("ing", PartOfSpeech::VBG),
+    ("ous", PartOfSpeech::JJ),
+    ("ful", PartOfSpeech::JJ),
+    ("ive", PartOfSpeech::JJ),
+    ("est", PartOfSpeech::JJS),
+    ("ly", PartOfSpeech::RB),
+    ("ed", PartOfSpeech::VBD),
+    ("al", PartOfSpeech::JJ),
+    ("s", PartOfSpeech::NNS),
+];
+
+const AMBIGUOUS_WORDS: [(&str, &[(PartOfSpeech, f64)]); 4] = [
+    (
+        "record",
+        &[
+            (PartOfSpeech::NN, 0.55),
+            (PartOfSpeech::VB, 0.4),
+            (PartOfSpeech::VBP, 0.05),
+        ],
+    ),
+    (
+        "present",
+        &[
+            (PartOfSpeech::JJ, 0.5),
+            (PartOfSpeech::NN, 0.3),
+            (PartOfSpeech::VB, 0.2),
+        ],
+    ),
+    ("lead", &[(PartOfSpeech::VB, 0.6), (PartOfSpeech::NN, 0.4)]),
+    (
+        "object",
+        &[(PartOfSpeech::NN, 0.7), (PartOfSpeech::VB, 0.3)],
+    ),
+];
+
+const KNOWN_WORDS: [(&str, PartOfSpeech); 111] = [
+    ("and", PartOfSpeech::CC),
+    ("or", PartOfSpeech::CC),
+    ("but", PartOfSpeech::CC),
+    ("nor", PartOfSpeech::CC),
+    ("yet", PartOfSpeech::CC),
+    ("one", PartOfSpeech::CD),
+    ("two", PartOfSpeech::CD),
+    ("three", PartOfSpeech::CD),
+    ("the", PartOfSpeech::DT),
+    ("a", PartOfSpeech::DT),
+    ("an", PartOfSpeech::DT),
+    ("this", PartOfSpeech::DT),
+    ("that", PartOfSpeech::DT),
+    ("these", PartOfSpeech::DT),
+    ("those", PartOfSpeech::DT),
+    ("each", PartOfSpeech::DT),
+    ("every", PartOfSpeech::DT),
+    ("some", PartOfSpeech::DT),
+    ("any", PartOfSpeech::DT),
+    ("no", PartOfSpeech::DT),
+    ("there", PartOfSpeech::EX),
+    ("of", PartOfSpeech::IN),
+    ("in", PartOfSpeech::IN),
+    ("on", PartOfSpeech::IN),
+    ("at", PartOfSpeech::IN),
+    ("by", PartOfSpeech::IN),
+    ("for", PartOfSpeech::IN),
+    ("with", PartOfSpeech::IN),
+    ("from", PartOfSpeech::IN),
+    ("about", PartOfSpeech::IN),
+    ("as", PartOfSpeech::IN),
+    ("into", PartOfSpeech::IN),
+    ("over", PartOfSpeech::IN),
+    ("under", PartOfSpeech::IN),
+    ("if", PartOfSpeech::IN),
+    ("because", PartOfSpeech::IN),
+    ("while", PartOfSpeech::IN),
+    ("before", PartOfSpeech::IN),
+    ("after", PartOfSpeech::IN),
+    ("than", PartOfSpeech::IN),
+    ("through", PartOfSpeech::IN),
+    ("without", PartOfSpeech::IN),
+    ("good", PartOfSpeech::JJ),
+    ("new", PartOfSpeech::JJ),
+    ("quick", PartOfSpeech::JJ),
+    ("lazy", PartOfSpeech::JJ),
+    ("brown", PartOfSpeech::JJ),
+    ("better", PartOfSpeech::JJR),
+    ("best", PartOfSpeech::JJS),
+    ("can", PartOfSpeech::MD),
+    ("could", PartOfSpeech::MD),
+    ("will", PartOfSpeech::MD),
+    ("would", PartOfSpeech::MD),
+    ("shall", PartOfSpeech::MD),
+    ("should", PartOfSpeech::MD),
+    ("may", PartOfSpeech::MD),
+    ("might", PartOfSpeech::MD),
+    ("must", PartOfSpeech::MD),
+    ("text", PartOfSpeech::NN),
+    ("word", PartOfSpeech::NN),
+    ("fox", PartOfSpeech::NN),
+    ("dog", PartOfSpeech::NN),
+    ("all", PartOfSpeech::PDT),
+    ("both", PartOfSpeech::PDT),
+    ("i", PartOfSpeech::PRP),
+    ("you", PartOfSpeech::PRP),
+    ("he", PartOfSpeech::PRP),
+    ("she", PartOfSpeech::PRP),
+    ("it", PartOfSpeech::PRP),
+    ("we", PartOfSpeech::PRP),
+    ("they", PartOfSpeech::PRP),
+    ("me", PartOfSpeech::PRP),
+    ("him", PartOfSpeech::PRP),
+    ("her", PartOfSpeech::PRP),
+    ("us", PartOfSpeech::PRP),
+    ("them", PartOfSpeech::PRP),
+    ("my", PartOfSpeech::PRP),
+    ("your", PartOfSpeech::PRP),
+    ("our", PartOfSpeech::PRP),
+    ("their", PartOfSpeech::PRP),
+    ("not", PartOfSpeech::RB),
+    ("very", PartOfSpeech::RB),
+    ("also", PartOfSpeech::RB),
+    ("more", PartOfSpeech::RBR),
+    ("most", PartOfSpeech::RBS),
+    ("up", PartOfSpeech::RP),
+    ("out", PartOfSpeech::RP),
+    ("to", PartOfSpeech::TO),
+    ("oh", PartOfSpeech::UH),
+    ("please", PartOfSpeech::UH),
+    ("be", PartOfSpeech::VB),
+    ("do", PartOfSpeech::VB),
+    ("run", PartOfSpeech::VB),
+    ("was", PartOfSpeech::VBD),
+    ("were", PartOfSpeech::VBD),
+    ("had", PartOfSpeech::VBD),
+    ("did", PartOfSpeech::VBD),
+    ("been", PartOfSpeech::VBN),
+    ("are", PartOfSpeech::VBP),
+    ("am", PartOfSpeech::VBP),
+    ("have", PartOfSpeech::VBP),
+    ("is", PartOfSpeech::VBZ),
+    ("has", PartOfSpeech::VBZ),
+    ("does", PartOfSpeech::VBZ),
+    ("which", PartOfSpeech::WDT),
+    ("who", PartOfSpeech::WP),
+    ("what", PartOfSpeech::WP),
+    ("where", PartOfSpeech::WRB),
+    ("when", PartOfSpeech::WRB),
+    ("why", PartOfSpeech::WRB),
+    ("how", PartOfSpeech::WRB),
+];
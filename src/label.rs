@@ -0,0 +1,100 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::*;
+
+/// Label set of a token classification model,
+/// as in the `id2label` table of its `config.json`.
+///
+/// Labels named after Penn Treebank tags map onto [`PartOfSpeech`],
+/// and other labels can be mapped with [`LabelRegistry::with_alias`].
+/// Tokens with unmapped labels are tagged [`PartOfSpeech::O`],
+/// and their `label_index` still finds their [`LabelRegistry::name`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LabelRegistry {
+    /// Label names by label index.
+    names: BTreeMap<i64, String>,
+    /// Parts of speech of label names.
+    tags: HashMap<String, PartOfSpeech>,
+    /// Distinct parts of speech the labels map to, in label index order.
+    parts_of_speech: Vec<PartOfSpeech>,
+}
+
+impl LabelRegistry {
+    /// The [`PartOfSpeech`] labels, indexed by their numeric values.
+    pub fn penn() -> Self {
+        Self::from_id2label(
+            PartOfSpeech::ALL
+                .iter()
+                .map(|&tag| (tag as i64, format!("{tag:?}"))),
+        )
+    }
+
+    /// Registry of labels from an `id2label` table.
+    pub fn from_id2label(id2label: impl IntoIterator<Item = (i64, String)>) -> Self {
+        let names: BTreeMap<_, _> = id2label.into_iter().collect();
+        let tags = names
+            .values()
+            .filter_map(|name| Some((name.clone(), name.parse().ok()?)))
+            .collect();
+        let mut registry = Self {
+            names,
+            tags,
+            parts_of_speech: Vec::new(),
+        };
+        registry.collect_parts_of_speech();
+        registry
+    }
+
+    /// Map the label `name` onto `tag`.
+    pub fn with_alias(mut self, name: &str, tag: PartOfSpeech) -> Self {
+        self.tags.insert(name.into(), tag);
+        self.collect_parts_of_speech();
+        self
+    }
+
+    fn collect_parts_of_speech(&mut self) {
+        self.parts_of_speech.clear();
+        for name in self.names.values() {
+            if let Some(&tag) = self.tags.get(name) {
+                if !self.parts_of_speech.contains(&tag) {
+                    self.parts_of_speech.push(tag);
+                }
+            }
+        }
+    }
+
+    /// Name of the label at `label_index`.
+    pub fn name(&self, label_index: i64) -> Option<&str> {
+        self.names.get(&label_index).map(String::as_str)
+    }
+
+    /// Part of speech the label `name` maps onto.
+    pub fn tag(&self, name: &str) -> Option<PartOfSpeech> {
+        self.tags.get(name).copied()
+    }
+
+    /// Part of speech the label at `label_index` maps onto.
+    pub fn tag_at(&self, label_index: i64) -> Option<PartOfSpeech> {
+        self.tag(self.name(label_index)?)
+    }
+
+    /// Distinct parts of speech the labels map onto.
+    pub fn parts_of_speech(&self) -> &[PartOfSpeech] {
+        &self.parts_of_speech
+    }
+
+    /// Label indexes and names, in index order.
+    pub fn iter(&self) -> impl Iterator<Item = (i64, &str)> {
+        self.names
+            .iter()
+            .map(|(&label_index, name)| (label_index, name.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
@@ -1,49 +1,151 @@
 use std::str::FromStr;
 
+#[cfg(feature = "num")]
 use num_derive::{FromPrimitive, ToPrimitive};
+#[cfg(feature = "rust-bert")]
 use rust_bert::{
-    pipelines::{
-        pos_tagging::POSConfig,
-        token_classification::{Token, TokenClassificationModel},
-    },
+    pipelines::token_classification::{Token, TokenClassificationModel},
     RustBertError,
 };
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+#[cfg(feature = "rust-bert")]
+mod builder;
+#[cfg(feature = "rust-bert")]
+mod classifier;
+mod distribution;
+mod error;
+#[cfg(any(test, feature = "testing"))]
+mod fake;
+mod label;
+mod tagger;
+mod universal;
+mod window;
+mod word;
+
+#[cfg(feature = "rust-bert")]
+pub use builder::*;
+#[cfg(feature = "rust-bert")]
+pub use classifier::*;
+pub use distribution::*;
+pub use error::*;
+#[cfg(any(test, feature = "testing"))]
+pub use fake::*;
+pub use label::*;
+pub use tagger::*;
+pub use universal::*;
+pub use window::*;
+pub use word::*;
+
 /// Part-of-speech tagging model.
+///
+/// Its [`Tagger::predict_distribution`] runs a separate [`LabelClassifier`],
+/// because Rust-BERT's pipeline does not expose the label probabilities.
+#[cfg(feature = "rust-bert")]
 pub struct POSModel {
     pub model: TokenClassificationModel,
+    classifier: LabelClassifier,
+    labels: LabelRegistry,
+    max_input_length: usize,
 }
 
 /// Iterator over the result of a POSModel prediction.
-/// Implements `Iter<Item = Result<POSToken, PartOfSpeechError>>`.
-pub type POSTokenResultIter = std::iter::Map<
-    std::vec::IntoIter<Token>,
-    fn(Token) -> Result<POSToken, <POSToken as TryFrom<Token>>::Error>,
->;
+/// Implements `Iter<Item = Result<POSToken, Error>>`.
+#[cfg(feature = "rust-bert")]
+pub struct POSTokenResultIter<'a> {
+    tokens: std::vec::IntoIter<Token>,
+    labels: &'a LabelRegistry,
+}
+
+#[cfg(feature = "rust-bert")]
+impl Iterator for POSTokenResultIter<'_> {
+    type Item = Result<POSToken, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.tokens.next()?;
+        Some(POSToken::from_token(token, self.labels).map_err(Error::from))
+    }
 
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.tokens.size_hint()
+    }
+}
+
+/// Maximum sequence length of MobileBERT, in sub-word tokens.
+/// Models loaded with [`POSModelBuilder`] read theirs from the configuration.
+#[cfg(feature = "rust-bert")]
+const MOBILEBERT_MAX_LENGTH: usize = 512;
+
+#[cfg(feature = "rust-bert")]
 impl POSModel {
-    pub fn try_default() -> Result<Self, RustBertError> {
-        let model = TokenClassificationModel::new(POSConfig::default().into())?;
-        Ok(Self { model })
+    /// Download MobileBERT and load it.
+    pub fn try_default() -> Result<Self, Error> {
+        POSModelBuilder::default().build()
     }
 
     /// Predict [`POSToken`]s for `input`.
-    pub fn predict(&self, input: &str) -> POSTokenResultIter {
+    pub fn predict(&self, input: &str) -> POSTokenResultIter<'_> {
         let mut token_vecs = self.model.predict(&[input], true, false);
         debug_assert_eq!(1, token_vecs.len());
+        self.result_iter(token_vecs.pop().unwrap())
+    }
+
+    /// Predict [`POSToken`]s for each of `inputs` in batched forward passes.
+    pub fn predict_batch(&self, inputs: &[&str]) -> Vec<POSTokenResultIter<'_>> {
+        let token_vecs = self.model.predict(inputs, true, false);
+        debug_assert_eq!(inputs.len(), token_vecs.len());
         token_vecs
-            .pop()
-            .unwrap()
             .into_iter()
-            .map(POSToken::try_from)
+            .map(|tokens| self.result_iter(tokens))
+            .collect()
+    }
+
+    fn result_iter(&self, tokens: Vec<Token>) -> POSTokenResultIter<'_> {
+        POSTokenResultIter {
+            tokens: tokens.into_iter(),
+            labels: &self.labels,
+        }
     }
 }
 
-// SAFETY: We only read `model`, and do not alias any pointers.
+// NOTE: Rust-BERT's pipeline keeps the label probabilities private,
+// so `predict_distribution` runs the model's own `LabelClassifier`.
+#[cfg(feature = "rust-bert")]
+impl Tagger for POSModel {
+    fn predict(&self, input: &str) -> Vec<Result<POSToken, Error>> {
+        POSModel::predict(self, input).collect()
+    }
+
+    fn predict_distribution(&self, input: &str) -> Result<Vec<TokenDistribution>, Error> {
+        self.classifier.predict(input, &self.labels)
+    }
+
+    fn predict_batch(&self, inputs: &[&str]) -> Vec<Vec<Result<POSToken, Error>>> {
+        POSModel::predict_batch(self, inputs)
+            .into_iter()
+            .map(Iterator::collect)
+            .collect()
+    }
+
+    fn labels(&self) -> &LabelRegistry {
+        &self.labels
+    }
+
+    fn max_input_length(&self) -> usize {
+        self.max_input_length
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        self.model.get_tokenizer().tokenize(text).len()
+    }
+}
+
+// SAFETY: We only read `model` and `classifier`, and do not alias any pointers.
+#[cfg(feature = "rust-bert")]
 unsafe impl Send for POSModel {}
+#[cfg(feature = "rust-bert")]
 unsafe impl Sync for POSModel {}
 
 /// Parsed Token generated by a `TokenClassificationModel`
@@ -75,14 +177,20 @@ impl POSToken {
     }
 }
 
-impl TryFrom<Token> for POSToken {
-    type Error = PartOfSpeechError;
+#[cfg(feature = "rust-bert")]
+impl POSToken {
+    /// Convert `value`, tagging it with the part of speech its label maps onto
+    /// in `labels`, or [`PartOfSpeech::O`] if it maps onto none.
+    pub fn from_token(value: Token, labels: &LabelRegistry) -> Result<Self, PartOfSpeechError> {
+        let tag = labels.tag(&value.label).unwrap_or(PartOfSpeech::O);
+        Self::with_tag(value, tag)
+    }
 
-    fn try_from(value: Token) -> Result<Self, Self::Error> {
+    fn with_tag(value: Token, tag: PartOfSpeech) -> Result<Self, PartOfSpeechError> {
         let Token {
             text,
             score,
-            label,
+            label: _,
             label_index,
             sentence: _inaccurate_so_useless,
             index,
@@ -94,7 +202,7 @@ impl TryFrom<Token> for POSToken {
             Some(offset) => Ok(Self {
                 word: text,
                 score,
-                tag: label.parse()?,
+                tag,
                 label_index,
                 index,
                 word_index,
@@ -106,6 +214,17 @@ impl TryFrom<Token> for POSToken {
     }
 }
 
+#[cfg(feature = "rust-bert")]
+impl TryFrom<Token> for POSToken {
+    type Error = PartOfSpeechError;
+
+    /// Convert `value` whose label must be a [`PartOfSpeech`].
+    fn try_from(value: Token) -> Result<Self, Self::Error> {
+        let tag = value.label.parse()?;
+        Self::with_tag(value, tag)
+    }
+}
+
 /// Enum representing part-of-speech labels of MobileBERT, from
 /// <https://huggingface.co/mrm8488/mobilebert-finetuned-pos/resolve/main/config.json>.
 // NOTE: ChatGPT generated the docstrings, so they may be inaccurate.
@@ -186,6 +305,46 @@ pub enum PartOfSpeech {
 
 pub const N_PART_OF_SPEECH: u8 = 34;
 
+impl PartOfSpeech {
+    /// All parts of speech, in the order of their numeric values.
+    pub const ALL: [Self; N_PART_OF_SPEECH as usize] = [
+        Self::CC,
+        Self::CD,
+        Self::DT,
+        Self::EX,
+        Self::FW,
+        Self::IN,
+        Self::JJ,
+        Self::JJR,
+        Self::JJS,
+        Self::MD,
+        Self::NN,
+        Self::NNP,
+        Self::NNPS,
+        Self::NNS,
+        Self::O,
+        Self::PDT,
+        Self::POS,
+        Self::PRP,
+        Self::RB,
+        Self::RBR,
+        Self::RBS,
+        Self::RP,
+        Self::SYM,
+        Self::TO,
+        Self::UH,
+        Self::VB,
+        Self::VBD,
+        Self::VBG,
+        Self::VBN,
+        Self::VBP,
+        Self::VBZ,
+        Self::WDT,
+        Self::WP,
+        Self::WRB,
+    ];
+}
+
 impl FromStr for PartOfSpeech {
     type Err = PartOfSpeechError;
 
@@ -0,0 +1,75 @@
+use super::*;
+
+/// Backend that tags text with parts of speech.
+///
+/// [`POSModel`] is the MobileBERT implementation;
+/// other backends only need to produce [`POSToken`]s.
+pub trait Tagger: Send + Sync + 'static {
+    /// Predict [`POSToken`]s for `input`,
+    /// with offsets relative to `input`.
+    /// Tokens that fail to convert are reported as [`Error`]s among the results.
+    fn predict(&self, input: &str) -> Vec<Result<POSToken, Error>>;
+
+    /// Predict [`POSToken`]s for each of `inputs`,
+    /// by default one at a time.
+    /// Backends that batch should override this.
+    fn predict_batch(&self, inputs: &[&str]) -> Vec<Vec<Result<POSToken, Error>>> {
+        inputs.iter().map(|input| self.predict(input)).collect()
+    }
+
+    /// Predict [`TokenDistribution`]s for `input`,
+    /// with offsets relative to `input`.
+    /// By default, [`Error::NoDistribution`],
+    /// for backends that only know the predicted tag.
+    fn predict_distribution(&self, _input: &str) -> Result<Vec<TokenDistribution>, Error> {
+        Err(Error::NoDistribution)
+    }
+
+    /// Labels of this tagger and the parts of speech they map onto.
+    fn labels(&self) -> &LabelRegistry;
+
+    /// Maximum number of sub-word tokens the tagger sees in one pass.
+    fn max_input_length(&self) -> usize;
+
+    /// Number of sub-word tokens in `text`,
+    /// by default [`estimate_tokens`].
+    fn count_tokens(&self, text: &str) -> usize {
+        estimate_tokens(text)
+    }
+
+    /// Like [`Tagger::predict`], but also tags `input` longer than
+    /// [`Tagger::max_input_length`] using [`predict_windowed`].
+    fn predict_long(&self, input: &str) -> Vec<Result<POSToken, Error>> {
+        predict_windowed(self, input)
+    }
+}
+
+impl<T: Tagger + ?Sized> Tagger for Box<T> {
+    fn predict(&self, input: &str) -> Vec<Result<POSToken, Error>> {
+        (**self).predict(input)
+    }
+
+    fn predict_batch(&self, inputs: &[&str]) -> Vec<Vec<Result<POSToken, Error>>> {
+        (**self).predict_batch(inputs)
+    }
+
+    fn predict_distribution(&self, input: &str) -> Result<Vec<TokenDistribution>, Error> {
+        (**self).predict_distribution(input)
+    }
+
+    fn labels(&self) -> &LabelRegistry {
+        (**self).labels()
+    }
+
+    fn max_input_length(&self) -> usize {
+        (**self).max_input_length()
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        (**self).count_tokens(text)
+    }
+
+    fn predict_long(&self, input: &str) -> Vec<Result<POSToken, Error>> {
+        (**self).predict_long(input)
+    }
+}
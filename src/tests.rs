@@ -1,26 +1,313 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use super::*;
 
+#[cfg(feature = "rust-bert")]
 use std::time::Instant;
 
 use insta::assert_debug_snapshot;
-use tracing::{info, Level};
+#[cfg(feature = "rust-bert")]
+use tracing::info;
+use tracing::Level;
 use tracing_subscriber::EnvFilter;
 
+const PARAGRAPH: &str = "Extracts Part of Speech tags (Noun, Verb, Adjective…) from text. A lightweight pretrained model using MobileBERT is available for English.";
+
+#[cfg(feature = "rust-bert")]
 #[test]
 fn paragraph_pos() {
     init_tracing();
     let start = Instant::now();
     let model = POSModel::try_default().unwrap();
     info!("Took {}ms to load the model.", start.elapsed().as_millis());
-    let input = "Extracts Part of Speech tags (Noun, Verb, Adjective…) from text. A lightweight pretrained model using MobileBERT is available for English.";
     let start = Instant::now();
-    let output = model.predict(input);
+    let output = model.predict(PARAGRAPH);
     info!("Took {}ms to predict.", start.elapsed().as_millis());
     let mut parsed = output.collect::<Result<Vec<_>, _>>().unwrap();
     round_scores(&mut parsed);
     assert_debug_snapshot!(parsed);
 }
 
+#[test]
+fn fake_paragraph_pos() {
+    init_tracing();
+    let output = FakeTagger::default().predict(PARAGRAPH);
+    let parsed = output.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+    for token in &parsed {
+        let word = PARAGRAPH
+            .chars()
+            .skip(token.offset_begin as usize)
+            .take((token.offset_end - token.offset_begin) as usize)
+            .collect::<String>();
+        assert_eq!(token.word, word);
+    }
+    assert_debug_snapshot!(parsed);
+}
+
+#[test]
+fn fake_long_input() {
+    // Like Rust-BERT's pipeline, input past the maximum length is tagged too.
+    let tagger = FakeTagger::default().with_max_input_length(5);
+    let words = tagger
+        .predict("one two three four five")
+        .into_iter()
+        .map(|token| token.unwrap().word)
+        .collect::<Vec<_>>();
+    assert_eq!(words, ["one", "two", "three", "four", "five"]);
+}
+
+#[test]
+fn batch_prediction() {
+    let unwrap = |results: Vec<Result<POSToken, Error>>| {
+        results.into_iter().map(Result::unwrap).collect::<Vec<_>>()
+    };
+
+    let tagger = TestTagger::default();
+    let inputs = ["One input.", "", PARAGRAPH];
+    let batched = tagger.predict_batch(&inputs);
+    assert_eq!(batched.len(), inputs.len());
+    for (input, batch) in inputs.into_iter().zip(batched) {
+        assert_eq!(unwrap(FakeTagger::default().predict(input)), unwrap(batch));
+    }
+    assert_eq!(tagger.n_batches.load(Ordering::Relaxed), 1);
+    assert_eq!(tagger.n_predict.load(Ordering::Relaxed), 0);
+
+    // Windows of long input are tagged in one batch, in order.
+    let tagger = TestTagger {
+        tagger: FakeTagger::default().with_max_input_length(12),
+        ..Default::default()
+    };
+    let input = [PARAGRAPH; 3].join(" ");
+    let offsets = unwrap(predict_windowed(&tagger, &input))
+        .into_iter()
+        .map(|token| token.offset_begin)
+        .collect::<Vec<_>>();
+    assert!(offsets.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(tagger.n_batches.load(Ordering::Relaxed), 1);
+    assert_eq!(tagger.n_predict.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn word_aggregation() {
+    let piece = |word: &str, tag, score, word_index, offset_begin| POSToken {
+        word: word.into(),
+        score,
+        tag,
+        label_index: tag as i64,
+        index: offset_begin as u16,
+        word_index,
+        offset_begin,
+        offset_end: offset_begin + word.len() as u32,
+    };
+    let tokens = [
+        piece("Tok", PartOfSpeech::NNP, 0.5, 1, 0),
+        piece("eniz", PartOfSpeech::VB, 0.9, 1, 3),
+        piece("ers", PartOfSpeech::NNP, 0.3, 1, 7),
+        piece("split", PartOfSpeech::VBP, 0.8, 2, 11),
+        piece("words", PartOfSpeech::NNS, 0.7, 2, 17),
+    ];
+    let words = |strategy| {
+        aggregate_words(tokens.clone(), strategy)
+            .into_iter()
+            .map(|word| {
+                (
+                    word.word,
+                    word.tag,
+                    (word.score * 100.).round(),
+                    word.offset_begin,
+                    word.offset_end,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        words(AggregationStrategy::First),
+        [
+            ("Tokenizers".into(), PartOfSpeech::NNP, 50., 0, 10),
+            ("split".into(), PartOfSpeech::VBP, 80., 11, 16),
+            ("words".into(), PartOfSpeech::NNS, 70., 17, 22),
+        ]
+    );
+    assert_eq!(words(AggregationStrategy::Max)[0].1, PartOfSpeech::VB);
+    assert_eq!(words(AggregationStrategy::Max)[0].2, 90.);
+    assert_eq!(words(AggregationStrategy::Average)[0].1, PartOfSpeech::VB);
+    assert_eq!(words(AggregationStrategy::Average)[0].2, 30.);
+
+    let word = aggregate_words(tokens, AggregationStrategy::Max).remove(0);
+    let token = POSToken::from(word);
+    assert_eq!(
+        (token.tag, token.index, token.word_index),
+        (PartOfSpeech::VB, 0, 1)
+    );
+    assert_eq!(token.label_index, PartOfSpeech::VB as i64);
+}
+
+#[test]
+fn label_distribution() {
+    let tagger = FakeTagger::default();
+    let distributions = tagger
+        .predict_distribution("Please record the record.")
+        .unwrap();
+    let record = &distributions[1];
+    assert_eq!(record.token.word, "record");
+    assert_eq!(record.token.tag, PartOfSpeech::NN);
+    assert_eq!(
+        record.top_k(2),
+        [(PartOfSpeech::NN, 0.55), (PartOfSpeech::VB, 0.4)]
+    );
+    assert!(record.is_ambiguous(0.2));
+    let the = &distributions[2];
+    assert_eq!(the.top_k(1), [(PartOfSpeech::DT, 0.99)]);
+    assert_eq!(the.probabilities.len(), PartOfSpeech::ALL.len());
+    assert!(!the.is_ambiguous(0.2));
+    assert!(the.residual() < 1e-9);
+
+    // Taggers without probabilities have no distribution.
+    struct ArgMaxTagger(FakeTagger);
+    impl Tagger for ArgMaxTagger {
+        fn predict(&self, input: &str) -> Vec<Result<POSToken, Error>> {
+            self.0.predict(input)
+        }
+
+        fn labels(&self) -> &LabelRegistry {
+            self.0.labels()
+        }
+
+        fn max_input_length(&self) -> usize {
+            self.0.max_input_length()
+        }
+    }
+    let tagger = ArgMaxTagger(tagger);
+    assert!(matches!(
+        tagger.predict_distribution("record"),
+        Err(Error::NoDistribution)
+    ));
+    let record = TokenDistribution::from(tagger.predict("record").remove(0).unwrap());
+    assert_eq!(record.probabilities, [(PartOfSpeech::NN, 0.55)]);
+    assert_eq!(record.probability(PartOfSpeech::VB), 0.);
+    assert!((record.residual() - 0.45).abs() < 1e-9);
+}
+
+#[test]
+fn label_registry() {
+    use PartOfSpeech::*;
+    let penn = LabelRegistry::penn();
+    assert_eq!(penn.len(), PartOfSpeech::ALL.len());
+    assert_eq!(penn.parts_of_speech(), PartOfSpeech::ALL);
+    assert_eq!(penn.name(VBZ as i64), Some("VBZ"));
+    assert_eq!(FakeTagger::default().labels(), &penn);
+
+    let custom = LabelRegistry::from_id2label([
+        (2, "NOUN".to_owned()),
+        (0, "NN".to_owned()),
+        (1, "B-VERB".to_owned()),
+        (3, "VB".to_owned()),
+    ]);
+    assert_eq!(
+        custom.iter().collect::<Vec<_>>(),
+        [(0, "NN"), (1, "B-VERB"), (2, "NOUN"), (3, "VB")]
+    );
+    assert_eq!(custom.tag("NN"), Some(NN));
+    assert_eq!(custom.tag("NOUN"), None);
+    assert_eq!(custom.tag_at(3), Some(VB));
+    assert_eq!(custom.tag_at(4), None);
+    assert_eq!(custom.parts_of_speech(), [NN, VB]);
+
+    let aliased = custom.with_alias("NOUN", NNS).with_alias("B-VERB", VB);
+    assert_eq!(aliased.tag_at(2), Some(NNS));
+    assert_eq!(aliased.parts_of_speech(), [NN, VB, NNS]);
+}
+
+#[test]
+fn universal_pos() {
+    use PartOfSpeech::*;
+    let verbs = PartOfSpeech::ALL
+        .into_iter()
+        .filter(|tag| tag.coarse() == UniversalPos::VERB)
+        .collect::<Vec<_>>();
+    assert_eq!(verbs, [VB, VBD, VBG, VBN, VBP, VBZ]);
+    assert_eq!(UniversalPos::from(NNPS), UniversalPos::PROPN);
+    assert_eq!(UniversalPos::from(WDT), UniversalPos::DET);
+    assert_eq!(UniversalPos::from(MD), UniversalPos::AUX);
+    let token = POSToken {
+        tag: JJS,
+        ..Default::default()
+    };
+    assert_eq!(token.coarse(), UniversalPos::ADJ);
+}
+
+#[test]
+fn sentence_segments() {
+    let input = "One sentence. (Another one.) Yes!\n\nNew paragraph\nwithout period\n\n3.14 is pi.";
+    let segments = split_segments(input)
+        .into_iter()
+        .map(|range| &input[range])
+        .collect::<Vec<_>>();
+    assert_eq!(
+        segments,
+        [
+            "One sentence. ",
+            "(Another one.) ",
+            "Yes!\n\n",
+            "New paragraph\nwithout period\n\n",
+            "3.14 is pi."
+        ]
+    );
+    let paragraphs = split_paragraphs(input)
+        .into_iter()
+        .map(|range| &input[range])
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paragraphs,
+        [
+            "One sentence. (Another one.) Yes!\n\n",
+            "New paragraph\nwithout period\n\n",
+            "3.14 is pi."
+        ]
+    );
+}
+
+#[test]
+fn windowed_prediction() {
+    init_tracing();
+    let input = [PARAGRAPH; 8].join("\n\n");
+    // Number tokens after a special token, so the first index is not 1.
+    let whole = TestTagger {
+        index_offset: 1,
+        ..Default::default()
+    }
+    .predict(&input);
+    let tagger = TestTagger {
+        tagger: FakeTagger::default().with_max_input_length(40),
+        index_offset: 1,
+        ..Default::default()
+    };
+    assert!(tagger.count_tokens(&input) > tagger.max_input_length());
+    let windowed = tagger.predict_long(&input);
+    let spans = |results: Vec<Result<POSToken, Error>>| {
+        results
+            .into_iter()
+            .map(|token| {
+                let token = token.unwrap();
+                (token.word, token.tag, token.offset_begin, token.offset_end)
+            })
+            .collect::<Vec<_>>()
+    };
+    let indexes = windowed
+        .iter()
+        .map(|token| token.as_ref().unwrap().index)
+        .collect::<Vec<_>>();
+    assert!(indexes.windows(2).all(|pair| pair[0] < pair[1]));
+    // The first window is numbered as if tagged alone.
+    let first_indexes = |results: &[Result<POSToken, Error>]| {
+        let token = results[0].as_ref().unwrap();
+        (token.index, token.word_index)
+    };
+    assert_eq!(first_indexes(&whole), first_indexes(&windowed));
+    assert_eq!(spans(whole), spans(windowed));
+}
+
+#[cfg(feature = "rust-bert")]
 #[test]
 fn markdown_pos() {
     init_tracing();
@@ -45,8 +332,106 @@ fn markdown_pos() {
     assert_debug_snapshot!(parsed);
 }
 
+#[cfg(feature = "rust-bert")]
+#[test]
+fn model_builder_errors() {
+    use std::fs;
+
+    let model_dir = std::env::temp_dir().join("natural_syntax_model_builder_errors");
+    _ = fs::remove_dir_all(&model_dir);
+    fs::create_dir_all(&model_dir).unwrap();
+    let build = || POSModelBuilder::default().model_dir(&model_dir).build();
+    assert!(matches!(
+        build(),
+        Err(Error::ModelResource(ModelLoadError::MissingFile {
+            file: ModelFile::Weights,
+            ..
+        }))
+    ));
+    fs::write(model_dir.join("rust_model.ot"), "").unwrap();
+    fs::write(model_dir.join("vocab.txt"), "").unwrap();
+    fs::write(model_dir.join("config.json"), "{").unwrap();
+    assert!(matches!(
+        build(),
+        Err(Error::ModelResource(ModelLoadError::MalformedConfig { .. }))
+    ));
+    fs::write(model_dir.join("config.json"), r#"{"vocab_size": 1}"#).unwrap();
+    let err = build().err().unwrap().report();
+    assert!(
+        err.starts_with("Loading the model resources: Malformed"),
+        "{err}"
+    );
+    assert!(err.contains("id2label"), "{err}");
+    fs::write(
+        model_dir.join("config.json"),
+        r#"{"id2label": {"zero": "NN"}}"#,
+    )
+    .unwrap();
+    let err = build().err().unwrap().report();
+    assert!(err.contains("`zero`"), "{err}");
+    assert!(matches!(
+        POSModelBuilder::default()
+            .weights(model_dir.join("rust_model.ot"))
+            .build(),
+        Err(Error::ModelResource(ModelLoadError::Unspecified(
+            ModelFile::Config
+        )))
+    ));
+    fs::remove_dir_all(&model_dir).unwrap();
+}
+
+/// [`FakeTagger`] that tags batches in reverse order, as a batching backend
+/// may, counting calls to `predict` and `predict_batch`,
+/// and shifting token indexes by `index_offset`.
+#[derive(Default)]
+struct TestTagger {
+    tagger: FakeTagger,
+    index_offset: u16,
+    n_predict: AtomicUsize,
+    n_batches: AtomicUsize,
+}
+
+impl TestTagger {
+    fn tag(&self, input: &str) -> Vec<Result<POSToken, Error>> {
+        let mut results = self.tagger.predict(input);
+        for token in results.iter_mut().flatten() {
+            token.index += self.index_offset;
+            token.word_index += self.index_offset;
+        }
+        results
+    }
+}
+
+impl Tagger for TestTagger {
+    fn predict(&self, input: &str) -> Vec<Result<POSToken, Error>> {
+        self.n_predict.fetch_add(1, Ordering::Relaxed);
+        self.tag(input)
+    }
+
+    fn predict_batch(&self, inputs: &[&str]) -> Vec<Vec<Result<POSToken, Error>>> {
+        self.n_batches.fetch_add(1, Ordering::Relaxed);
+        let mut results = inputs
+            .iter()
+            .rev()
+            .map(|input| self.tag(input))
+            .collect::<Vec<_>>();
+        results.reverse();
+        results
+    }
+
+    fn labels(&self) -> &LabelRegistry {
+        self.tagger.labels()
+    }
+
+    fn max_input_length(&self) -> usize {
+        self.tagger.max_input_length()
+    }
+}
+
+#[cfg(feature = "rust-bert")]
 const PRECISION: f64 = 1e-3;
 
+#[cfg(feature = "rust-bert")]
 fn round_scores(predictions: &mut [POSToken]) {
     predictions
         .iter_mut()
@@ -0,0 +1,87 @@
+use super::*;
+
+/// Coarse part-of-speech tags of Universal Dependencies, from
+/// <https://universaldependencies.org/u/pos/>.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "num", derive(FromPrimitive, ToPrimitive))]
+pub enum UniversalPos {
+    /// Adjective
+    ADJ = 0,
+    /// Adposition
+    ADP = 1,
+    /// Adverb
+    ADV = 2,
+    /// Auxiliary
+    AUX = 3,
+    /// Coordinating conjunction
+    CCONJ = 4,
+    /// Determiner
+    DET = 5,
+    /// Interjection
+    INTJ = 6,
+    /// Noun
+    NOUN = 7,
+    /// Numeral
+    NUM = 8,
+    /// Particle
+    PART = 9,
+    /// Pronoun
+    PRON = 10,
+    /// Proper noun
+    PROPN = 11,
+    /// Punctuation
+    PUNCT = 12,
+    /// Subordinating conjunction
+    SCONJ = 13,
+    /// Symbol
+    SYM = 14,
+    /// Verb
+    VERB = 15,
+    /// Other
+    #[default]
+    X = 16,
+}
+
+pub const N_UNIVERSAL_POS: u8 = 17;
+
+impl From<PartOfSpeech> for UniversalPos {
+    /// Convert as per the Universal Dependencies mapping of Penn Treebank tags.
+    /// Lossy for `IN`, which is always an adposition here,
+    /// though it may also be a subordinating conjunction.
+    fn from(value: PartOfSpeech) -> Self {
+        value.coarse()
+    }
+}
+
+impl PartOfSpeech {
+    /// The coarse [`UniversalPos`] of this tag.
+    pub const fn coarse(self) -> UniversalPos {
+        match self {
+            Self::CC => UniversalPos::CCONJ,
+            Self::CD => UniversalPos::NUM,
+            Self::DT | Self::PDT | Self::WDT => UniversalPos::DET,
+            Self::EX | Self::PRP | Self::WP => UniversalPos::PRON,
+            Self::FW | Self::O => UniversalPos::X,
+            Self::IN | Self::RP => UniversalPos::ADP,
+            Self::JJ | Self::JJR | Self::JJS => UniversalPos::ADJ,
+            Self::MD => UniversalPos::AUX,
+            Self::NN | Self::NNS => UniversalPos::NOUN,
+            Self::NNP | Self::NNPS => UniversalPos::PROPN,
+            Self::POS | Self::TO => UniversalPos::PART,
+            Self::RB | Self::RBR | Self::RBS | Self::WRB => UniversalPos::ADV,
+            Self::SYM => UniversalPos::SYM,
+            Self::UH => UniversalPos::INTJ,
+            Self::VB | Self::VBD | Self::VBG | Self::VBN | Self::VBP | Self::VBZ => {
+                UniversalPos::VERB
+            }
+        }
+    }
+}
+
+impl POSToken {
+    /// The coarse [`UniversalPos`] of [`POSToken::tag`].
+    pub fn coarse(&self) -> UniversalPos {
+        self.tag.coarse()
+    }
+}
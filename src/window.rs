@@ -0,0 +1,229 @@
+use std::ops::Range;
+
+use super::*;
+
+/// Special tokens the model adds around each sequence.
+pub const N_SPECIAL_TOKENS: usize = 2;
+
+/// Predict [`POSToken`]s for `input` of any length with `tagger`.
+///
+/// `input` that exceeds [`Tagger::max_input_length`] is split at sentence or
+/// paragraph boundaries into windows, tagged with [`Tagger::predict_batch`].
+/// Each window starts with up to a quarter of its budget of
+/// preceding sentences as context,
+/// and only keeps tokens after that context,
+/// so overlapping tokens are not duplicated.
+/// Offsets are relative to `input`,
+/// and `index`/`word_index` continue from the first window across the others.
+pub fn predict_windowed<T: Tagger + ?Sized>(
+    tagger: &T,
+    input: &str,
+) -> Vec<Result<POSToken, Error>> {
+    let budget = tagger
+        .max_input_length()
+        .saturating_sub(N_SPECIAL_TOKENS)
+        .max(1);
+    if tagger.count_tokens(input) <= budget {
+        return tagger.predict(input);
+    }
+
+    let segments = split_segments(input)
+        .into_iter()
+        .flat_map(|range| split_oversized(tagger, input, range, budget))
+        .collect::<Vec<_>>();
+    let windows = windows(&segments, budget);
+    let texts = windows
+        .iter()
+        .map(|window| {
+            &input[segments[window.context].range.start..segments[window.end - 1].range.end]
+        })
+        .collect::<Vec<_>>();
+    let window_results = tagger.predict_batch(&texts);
+
+    let mut results = Vec::new();
+    // Indexes of the last token of the previous windows, if any.
+    let mut last_indexes = None;
+    // Windows start in order, so char offsets are counted from the previous start.
+    let (mut byte_begin, mut char_begin) = (0, 0);
+    for (window, window_result) in windows.into_iter().zip(window_results) {
+        let window_byte_begin = segments[window.context].range.start;
+        debug_assert!(byte_begin <= window_byte_begin, "Windows out of order.");
+        char_begin += input[byte_begin..window_byte_begin].chars().count() as u32;
+        byte_begin = window_byte_begin;
+        let owned_char_begin = char_begin
+            + input[byte_begin..segments[window.start].range.start]
+                .chars()
+                .count() as u32;
+        let mut first_indexes = None;
+        for result in window_result {
+            let mut token = match result {
+                Ok(token) => token,
+                Err(err) => {
+                    results.push(Err(err));
+                    continue;
+                }
+            };
+            token.offset_begin += char_begin;
+            token.offset_end += char_begin;
+            if token.offset_begin < owned_char_begin {
+                continue;
+            }
+            let (first_index, first_word_index) =
+                *first_indexes.get_or_insert((token.index, token.word_index));
+            // The first window keeps the indexes of tagging it alone.
+            if let Some((last_index, last_word_index)) = last_indexes {
+                token.index = shift_index(last_index, token.index, first_index);
+                token.word_index = shift_index(last_word_index, token.word_index, first_word_index);
+            }
+            results.push(Ok(token));
+        }
+        if let Some(token) = results.iter().rev().flatten().next() {
+            last_indexes = Some((token.index, token.word_index));
+        }
+    }
+    results
+}
+
+/// Continue numbering from `last` for `index` in a window starting at `first`.
+fn shift_index(last: u16, index: u16, first: u16) -> u16 {
+    last.saturating_add(index.saturating_sub(first))
+        .saturating_add(1)
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Segment {
+    /// Byte range in the input.
+    range: Range<usize>,
+    n_tokens: usize,
+}
+
+/// Window of segments `context..end`, owning segments `start..end`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Window {
+    context: usize,
+    start: usize,
+    end: usize,
+}
+
+fn windows(segments: &[Segment], budget: usize) -> Vec<Window> {
+    let mut windows = Vec::new();
+    let mut start = 0;
+    while start < segments.len() {
+        let context_budget = (budget / 4).min(budget.saturating_sub(segments[start].n_tokens));
+        let (mut context, mut n_tokens) = (start, 0);
+        while context > 0 && n_tokens + segments[context - 1].n_tokens <= context_budget {
+            context -= 1;
+            n_tokens += segments[context].n_tokens;
+        }
+        let mut end = start;
+        while end < segments.len() && (end == start || n_tokens + segments[end].n_tokens <= budget)
+        {
+            n_tokens += segments[end].n_tokens;
+            end += 1;
+        }
+        windows.push(Window {
+            context,
+            start,
+            end,
+        });
+        start = end;
+    }
+    windows
+}
+
+/// Split `input` into byte ranges of sentences and paragraphs,
+/// each including its trailing whitespace.
+pub fn split_segments(input: &str) -> Vec<Range<usize>> {
+    split(input, true)
+}
+
+/// Split `input` into byte ranges of paragraphs separated by blank lines,
+/// each including its trailing whitespace.
+pub fn split_paragraphs(input: &str) -> Vec<Range<usize>> {
+    split(input, false)
+}
+
+fn split(input: &str, at_sentences: bool) -> Vec<Range<usize>> {
+    let mut segments = Vec::new();
+    let (mut segment_start, mut n_newlines) = (0, 0);
+    let (mut after_terminal, mut boundary) = (false, false);
+    for (index, char) in input.char_indices() {
+        if char.is_whitespace() {
+            n_newlines += (char == '\n') as usize;
+            boundary |= (at_sentences && after_terminal) || n_newlines >= 2;
+            after_terminal = false;
+        } else {
+            if boundary && index > segment_start {
+                segments.push(segment_start..index);
+                segment_start = index;
+            }
+            (boundary, n_newlines) = (false, 0);
+            after_terminal = matches!(char, '.' | '!' | '?')
+                || (after_terminal && matches!(char, '"' | '\'' | ')' | ']' | '”' | '’'));
+        }
+    }
+    if segment_start < input.len() {
+        segments.push(segment_start..input.len());
+    }
+    segments
+}
+
+/// Split a segment with more tokens than `budget` at whitespace.
+/// A single word over `budget` is left for the tagger to split.
+fn split_oversized<T: Tagger + ?Sized>(
+    tagger: &T,
+    input: &str,
+    range: Range<usize>,
+    budget: usize,
+) -> Vec<Segment> {
+    let n_tokens = tagger.count_tokens(&input[range.clone()]);
+    if n_tokens <= budget {
+        return vec![Segment { range, n_tokens }];
+    }
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut previous_is_whitespace = false;
+    let word_starts = input[range.clone()]
+        .char_indices()
+        .filter_map(|(index, char)| {
+            let is_word_start = previous_is_whitespace && !char.is_whitespace();
+            previous_is_whitespace = char.is_whitespace();
+            is_word_start.then_some(range.start + index)
+        })
+        .collect::<Vec<_>>();
+    let mut word_start = range.start;
+    for word_end in word_starts.into_iter().chain([range.end]) {
+        let n_tokens = tagger.count_tokens(&input[word_start..word_end]);
+        match segments.last_mut() {
+            Some(last) if last.n_tokens + n_tokens <= budget => {
+                last.range.end = word_end;
+                last.n_tokens += n_tokens;
+            }
+            _ => segments.push(Segment {
+                range: word_start..word_end,
+                n_tokens,
+            }),
+        }
+        word_start = word_end;
+    }
+    segments
+}
+
+/// Estimate the number of sub-word tokens in `text`,
+/// counting four characters per token in each word,
+/// and one token per punctuation.
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut n_tokens = 0;
+    let mut word_length: usize = 0;
+    for char in text.chars() {
+        if char.is_alphanumeric() {
+            word_length += 1;
+            continue;
+        }
+        n_tokens += word_length.div_ceil(4);
+        word_length = 0;
+        if !char.is_whitespace() {
+            n_tokens += 1;
+        }
+    }
+    n_tokens + word_length.div_ceil(4)
+}
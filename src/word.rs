@@ -0,0 +1,144 @@
+use super::*;
+
+/// Whole word aggregated from consecutive sub-word [`POSToken`]s that
+/// share a `word_index`.
+#[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Word {
+    /// String representation of the word
+    pub word: String,
+    /// Combined confidence score
+    pub score: f64,
+    /// Part-of-speech tag
+    pub tag: PartOfSpeech,
+    /// Word position index
+    pub word_index: u16,
+    /// Word offset beginning (in unicode points) relative to the input string
+    pub offset_begin: u32,
+    /// Word offset end (in unicode points) relative to the input string
+    pub offset_end: u32,
+    /// Sub-word tokens making up the word
+    pub tokens: Vec<POSToken>,
+}
+
+/// How to combine the tags and scores of sub-word tokens into a [`Word`].
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize, Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub enum AggregationStrategy {
+    /// Tag and score of the first token.
+    #[default]
+    First,
+    /// Tag and score of the token with the highest score.
+    Max,
+    /// Tag with the highest score averaged over all tokens,
+    /// where tokens with other tags score zero.
+    Average,
+}
+
+impl AggregationStrategy {
+    /// Choose the tag and score for non-empty `tokens`.
+    fn combine(self, tokens: &[POSToken]) -> (PartOfSpeech, f64) {
+        match self {
+            Self::First => (tokens[0].tag, tokens[0].score),
+            Self::Max => {
+                tokens
+                    .iter()
+                    .skip(1)
+                    .fold((tokens[0].tag, tokens[0].score), |max, token| {
+                        if token.score > max.1 {
+                            (token.tag, token.score)
+                        } else {
+                            max
+                        }
+                    })
+            }
+            Self::Average => {
+                let mut best = (tokens[0].tag, f64::NEG_INFINITY);
+                for (index, token) in tokens.iter().enumerate() {
+                    if tokens[..index].iter().any(|seen| seen.tag == token.tag) {
+                        continue;
+                    }
+                    let total: f64 = tokens
+                        .iter()
+                        .filter(|other| other.tag == token.tag)
+                        .map(|other| other.score)
+                        .sum();
+                    let average = total / tokens.len() as f64;
+                    if average > best.1 {
+                        best = (token.tag, average);
+                    }
+                }
+                best
+            }
+        }
+    }
+}
+
+/// Group consecutive `tokens` with the same `word_index` and
+/// adjacent offsets into [`Word`]s, combined using `strategy`.
+pub fn aggregate_words(
+    tokens: impl IntoIterator<Item = POSToken>,
+    strategy: AggregationStrategy,
+) -> Vec<Word> {
+    let mut groups: Vec<Vec<POSToken>> = Vec::new();
+    for token in tokens {
+        match groups.last_mut() {
+            Some(group)
+                if group.last().is_some_and(|last| {
+                    last.word_index == token.word_index && last.offset_end == token.offset_begin
+                }) =>
+            {
+                group.push(token)
+            }
+            _ => groups.push(vec![token]),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|tokens| {
+            let (tag, score) = strategy.combine(&tokens);
+            Word {
+                word: tokens.iter().map(|token| token.word.as_str()).collect(),
+                score,
+                tag,
+                word_index: tokens[0].word_index,
+                offset_begin: tokens[0].offset_begin,
+                offset_end: tokens[tokens.len() - 1].offset_end,
+                tokens,
+            }
+        })
+        .collect()
+}
+
+impl From<Word> for POSToken {
+    /// Word-level token with the `label_index` of the token whose tag won.
+    fn from(value: Word) -> Self {
+        let Word {
+            word,
+            score,
+            tag,
+            word_index,
+            offset_begin,
+            offset_end,
+            tokens,
+        } = value;
+        let label_index = tokens
+            .iter()
+            .find(|token| token.tag == tag)
+            .map_or(tag as i64, |token| token.label_index);
+        Self {
+            word,
+            score,
+            tag,
+            label_index,
+            index: tokens.first().map_or(0, |token| token.index),
+            word_index,
+            offset_begin,
+            offset_end,
+        }
+    }
+}